@@ -0,0 +1,189 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{errors::WalletError, ethereum::abi::keccak256};
+use serde::Deserialize;
+
+/// Number of hex digits in an address, without the `0x` prefix.
+const ADDRESS_HEX_LEN: usize = 40;
+
+/// A validated 20-byte EVM address.
+///
+/// Stored in lowercase so it can be compared and used as a cache key
+/// directly, and displayed in its EIP-55 mixed-case checksum form.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Address(String);
+
+impl Address {
+    /// Parses an address, ignoring surrounding whitespace.
+    ///
+    /// Lowercase and uppercase hex are accepted as is. Mixed-case input is
+    /// treated as EIP-55 and must carry a valid checksum, so a mistyped
+    /// character is caught instead of silently tracking the wrong wallet.
+    ///
+    /// # Errors
+    /// Returns [`WalletError::InvalidAddress`] describing why the input was rejected.
+    pub fn parse(input: &str) -> Result<Address, WalletError> {
+        let input = input.trim();
+        let invalid = |reason: String| WalletError::InvalidAddress {
+            address: input.to_string(),
+            reason,
+        };
+
+        if input.is_empty() {
+            return Err(invalid("the address is empty".to_string()));
+        }
+        let digits = input
+            .strip_prefix("0x")
+            .ok_or_else(|| invalid("it must start with 0x".to_string()))?;
+        if digits.len() != ADDRESS_HEX_LEN {
+            return Err(invalid(format!(
+                "it must have {} hex digits after 0x, found {}",
+                ADDRESS_HEX_LEN,
+                digits.len()
+            )));
+        }
+        if let Some(character) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(invalid(format!("'{}' is not a hex digit", character)));
+        }
+
+        let address = Address(format!("0x{}", digits.to_lowercase()));
+
+        let has_lower = digits.chars().any(|c| c.is_ascii_lowercase());
+        let has_upper = digits.chars().any(|c| c.is_ascii_uppercase());
+        if has_lower && has_upper && address.to_checksum() != input {
+            return Err(invalid(format!(
+                "EIP-55 checksum mismatch, expected {}",
+                address.to_checksum()
+            )));
+        }
+
+        Ok(address)
+    }
+
+    /// Returns the normalized lowercase form, `0x`-prefixed.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the EIP-55 checksummed form of the address.
+    pub fn to_checksum(&self) -> String {
+        let digits = &self.0[2..];
+
+        let hash = keccak256(digits.as_bytes());
+
+        let checksummed: String = digits
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect();
+
+        format!("0x{}", checksummed)
+    }
+}
+
+impl FromStr for Address {
+    type Err = WalletError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Address::parse(input)
+    }
+}
+
+impl TryFrom<String> for Address {
+    type Error = WalletError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        Address::parse(&input)
+    }
+}
+
+impl fmt::Display for Address {
+    /// Formats the address with its EIP-55 checksum.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_checksum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checksummed addresses from the EIP-55 specification.
+    const CHECKSUMMED: [&str; 4] = [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    #[test]
+    fn checksum_round_trips() {
+        for input in CHECKSUMMED {
+            let address = Address::parse(input).unwrap();
+            assert_eq!(address.as_str(), input.to_lowercase());
+            assert_eq!(address.to_checksum(), input);
+            assert_eq!(address.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn single_case_input_needs_no_checksum() {
+        let lower = Address::parse("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        let upper = Address::parse("0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED").unwrap();
+        assert_eq!(lower, upper);
+        assert_eq!(lower.to_checksum(), CHECKSUMMED[0]);
+    }
+
+    #[test]
+    fn surrounding_whitespace_is_ignored() {
+        let address = Address::parse(&format!("  {}\n", CHECKSUMMED[1])).unwrap();
+        assert_eq!(address.to_checksum(), CHECKSUMMED[1]);
+    }
+
+    #[test]
+    fn bad_checksum_is_rejected() {
+        // Last character flipped to uppercase.
+        let error = Address::parse("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD").unwrap_err();
+        assert!(matches!(
+            error,
+            WalletError::InvalidAddress { reason, .. } if reason.contains("checksum")
+        ));
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        for input in [
+            "",
+            "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed00",
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaeg",
+        ] {
+            assert!(
+                matches!(
+                    Address::parse(input),
+                    Err(WalletError::InvalidAddress { .. })
+                ),
+                "{:?} should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn from_str_and_deserialize_validate() {
+        assert!("0xnot-an-address".parse::<Address>().is_err());
+        let address: Address = serde_json::from_str(&format!("\"{}\"", CHECKSUMMED[2])).unwrap();
+        assert_eq!(address.to_checksum(), CHECKSUMMED[2]);
+        assert!(serde_json::from_str::<Address>("\"0x1234\"").is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub};
+
+use primitive_types::U256;
+
+use crate::errors::WalletError;
+
+/// Number of decimals of one Ether expressed in wei.
+pub const ETHER_DECIMALS: u8 = 18;
+
+/// Number of decimals of one gwei expressed in wei.
+pub const GWEI_DECIMALS: u8 = 9;
+
+/// An exact on-chain amount in wei, backed by a 256-bit unsigned integer.
+///
+/// Balances and transaction values are kept as integers end to end and only
+/// converted to a decimal string when displayed or exported, so no precision
+/// is lost on large values or fractional Ether amounts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Wei(pub U256);
+
+impl Wei {
+    /// Zero wei.
+    pub const ZERO: Wei = Wei(U256::zero());
+
+    /// Parses a base-10 wei amount, as returned by Etherscan.
+    pub fn from_dec_str(value: &str) -> Result<Wei, WalletError> {
+        U256::from_dec_str(value)
+            .map(Wei)
+            .map_err(|_| WalletError::AmountParse {
+                value: value.to_string(),
+            })
+    }
+
+    /// Parses a `0x`-prefixed hexadecimal wei amount, as returned by JSON-RPC.
+    pub fn from_hex_str(value: &str) -> Result<Wei, WalletError> {
+        let digits = value.strip_prefix("0x").unwrap_or(value);
+        if digits.is_empty() {
+            return Ok(Wei::ZERO);
+        }
+
+        U256::from_str_radix(digits, 16)
+            .map(Wei)
+            .map_err(|_| WalletError::AmountParse {
+                value: value.to_string(),
+            })
+    }
+
+    /// Formats the amount as an integer number of wei.
+    pub fn to_wei_string(&self) -> String {
+        self.0.to_string()
+    }
+
+    /// Formats the amount in gwei with all significant decimals.
+    pub fn to_gwei_string(&self) -> String {
+        format_units(self.0, GWEI_DECIMALS)
+    }
+
+    /// Formats the amount in Ether with all significant decimals.
+    pub fn to_ether_string(&self) -> String {
+        format_units(self.0, ETHER_DECIMALS)
+    }
+
+    /// Approximates the amount in Ether as a float, for fiat conversion only.
+    pub fn to_ether_f64(&self) -> f64 {
+        self.to_ether_string().parse().unwrap_or(0.0)
+    }
+
+    /// Returns the integer average of `total` over `count` items, or zero when `count` is zero.
+    pub fn average(total: Wei, count: usize) -> Wei {
+        match count {
+            0 => Wei::ZERO,
+            count => Wei(total.0 / U256::from(count)),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Wei {
+    /// Returns `milli` thousandths of one Ether.
+    pub(crate) fn milliether(milli: u64) -> Wei {
+        Wei(U256::from(milli) * U256::exp10(15))
+    }
+}
+
+impl fmt::Display for Wei {
+    /// Formats the amount in Ether.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_ether_string())
+    }
+}
+
+impl Add for Wei {
+    type Output = Wei;
+
+    fn add(self, other: Wei) -> Wei {
+        Wei(self.0.saturating_add(other.0))
+    }
+}
+
+impl AddAssign for Wei {
+    fn add_assign(&mut self, other: Wei) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Wei {
+    type Output = Wei;
+
+    fn sub(self, other: Wei) -> Wei {
+        Wei(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Sum for Wei {
+    fn sum<I: Iterator<Item = Wei>>(iter: I) -> Wei {
+        iter.fold(Wei::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Wei> for Wei {
+    fn sum<I: Iterator<Item = &'a Wei>>(iter: I) -> Wei {
+        iter.copied().sum()
+    }
+}
+
+/// Formats `credit - debit` in Ether, prefixed with `-` when negative.
+pub fn format_ether_delta(credit: Wei, debit: Wei) -> String {
+    if credit >= debit {
+        (credit - debit).to_ether_string()
+    } else {
+        format!("-{}", (debit - credit).to_ether_string())
+    }
+}
+
+/// Formats an integer amount with the given number of decimals.
+///
+/// Trailing zeros of the fractional part are dropped, so `1500000000000000000`
+/// with 18 decimals becomes `1.5` and whole amounts have no decimal point.
+pub fn format_units(value: U256, decimals: u8) -> String {
+    let digits = value.to_string();
+    let decimals = decimals as usize;
+
+    let (integer, fraction) = if digits.len() > decimals {
+        let split = digits.len() - decimals;
+        (digits[..split].to_string(), digits[split..].to_string())
+    } else {
+        (
+            "0".to_string(),
+            format!("{:0>width$}", digits, width = decimals),
+        )
+    };
+
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ether(value: u64) -> Wei {
+        Wei(U256::from(value) * U256::exp10(ETHER_DECIMALS as usize))
+    }
+
+    #[test]
+    fn addition_saturates() {
+        assert_eq!(ether(1) + ether(2), ether(3));
+        assert_eq!(Wei(U256::MAX) + Wei(U256::one()), Wei(U256::MAX));
+
+        let mut total = Wei(U256::MAX - 1);
+        total += ether(1);
+        assert_eq!(total, Wei(U256::MAX));
+    }
+
+    #[test]
+    fn subtraction_saturates_at_zero() {
+        assert_eq!(ether(3) - ether(1), ether(2));
+        assert_eq!(ether(1) - ether(3), Wei::ZERO);
+    }
+
+    #[test]
+    fn sum_saturates() {
+        let amounts = [Wei(U256::MAX), ether(1), ether(2)];
+        assert_eq!(amounts.iter().sum::<Wei>(), Wei(U256::MAX));
+        assert_eq!([ether(1), ether(2)].into_iter().sum::<Wei>(), ether(3));
+        assert_eq!(std::iter::empty::<Wei>().sum::<Wei>(), Wei::ZERO);
+    }
+
+    #[test]
+    fn average_of_nothing_is_zero() {
+        assert_eq!(Wei::average(ether(3), 0), Wei::ZERO);
+        assert_eq!(
+            Wei::average(ether(3), 2),
+            Wei::from_dec_str("1500000000000000000").unwrap()
+        );
+    }
+
+    #[test]
+    fn formats_ether_and_gwei() {
+        assert_eq!(Wei::ZERO.to_ether_string(), "0");
+        assert_eq!(ether(2).to_ether_string(), "2");
+        assert_eq!(Wei(U256::one()).to_ether_string(), "0.000000000000000001");
+        assert_eq!(
+            Wei::from_dec_str("1500000000000000000")
+                .unwrap()
+                .to_string(),
+            "1.5"
+        );
+        assert_eq!(
+            Wei::from_dec_str("21500000000").unwrap().to_gwei_string(),
+            "21.5"
+        );
+        assert_eq!(ether(1).to_wei_string(), "1000000000000000000");
+        assert_eq!(
+            Wei::from_dec_str("250000000000000000")
+                .unwrap()
+                .to_ether_f64(),
+            0.25
+        );
+    }
+
+    #[test]
+    fn formats_signed_delta() {
+        assert_eq!(format_ether_delta(ether(3), ether(1)), "2");
+        assert_eq!(format_ether_delta(ether(1), ether(3)), "-2");
+        assert_eq!(format_ether_delta(ether(1), ether(1)), "0");
+    }
+
+    #[test]
+    fn parses_decimal_and_hex() {
+        assert_eq!(Wei::from_dec_str("1000000000000000000").unwrap(), ether(1));
+        assert_eq!(Wei::from_hex_str("0xde0b6b3a7640000").unwrap(), ether(1));
+        assert_eq!(Wei::from_hex_str("0x").unwrap(), Wei::ZERO);
+        assert!(matches!(
+            Wei::from_dec_str("1.5"),
+            Err(WalletError::AmountParse { .. })
+        ));
+        assert!(matches!(
+            Wei::from_hex_str("0xzz"),
+            Err(WalletError::AmountParse { .. })
+        ));
+    }
+}
@@ -0,0 +1,392 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use chrono::{Datelike, Days, Months, NaiveDate};
+use clap::ValueEnum;
+use csv::Writer;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    address::Address,
+    amount::Wei,
+    enums::Direction,
+    errors::WalletError,
+    ethereum::{chain::Chain, utils::utc_date},
+    structs::Transaction,
+};
+
+/// Spacing of the points of a balance history.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SamplingInterval {
+    /// One point per UTC day.
+    Daily,
+    /// One point per week, starting on Monday.
+    Weekly,
+    /// One point per calendar month.
+    #[default]
+    Monthly,
+}
+
+impl SamplingInterval {
+    /// Returns the first day of the period containing `date`.
+    pub fn period_start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            SamplingInterval::Daily => date,
+            SamplingInterval::Weekly => {
+                date - Days::new(date.weekday().num_days_from_monday() as u64)
+            }
+            SamplingInterval::Monthly => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    /// Returns the first day of the period following the one starting on `start`.
+    pub fn next_period(self, start: NaiveDate) -> NaiveDate {
+        let next = match self {
+            SamplingInterval::Daily => start.checked_add_days(Days::new(1)),
+            SamplingInterval::Weekly => start.checked_add_days(Days::new(7)),
+            SamplingInterval::Monthly => start.checked_add_months(Months::new(1)),
+        };
+        next.unwrap_or(NaiveDate::MAX)
+    }
+}
+
+impl fmt::Display for SamplingInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            SamplingInterval::Daily => "daily",
+            SamplingInterval::Weekly => "weekly",
+            SamplingInterval::Monthly => "monthly",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Balance of a wallet at the end of one period, with what moved during it.
+#[derive(Debug, Clone)]
+pub struct BalancePoint {
+    /// First day of the period.
+    pub date: NaiveDate,
+    /// Balance at the end of the period.
+    pub balance: Wei,
+    /// Value received during the period.
+    pub inflow: Wei,
+    /// Value sent during the period.
+    pub outflow: Wei,
+    /// Fees paid during the period.
+    pub fees: Wei,
+}
+
+/// Native-currency balance of a wallet over time, rebuilt from its history.
+#[derive(Debug)]
+pub struct BalanceTimeline {
+    /// Chain the history belongs to.
+    pub chain: Chain,
+    /// Wallet the history belongs to.
+    pub address: Address,
+    /// Spacing of the points.
+    pub interval: SamplingInterval,
+    /// One point per period, oldest first, from the first transaction to the last period.
+    pub points: Vec<BalancePoint>,
+}
+
+impl BalanceTimeline {
+    /// Returns the reconstructed balance at the end of the last period.
+    pub fn final_balance(&self) -> Wei {
+        self.points.last().map_or(Wei::ZERO, |point| point.balance)
+    }
+
+    /// Writes the series to a CSV file with one row per point.
+    pub fn write_csv(&self, path: &Path) -> Result<(), WalletError> {
+        let mut writer = Writer::from_path(path)?;
+
+        writer.write_record(["Date", "Balance", "Inflow", "Outflow", "Fees"])?;
+        for point in &self.points {
+            writer.write_record([
+                point.date.to_string(),
+                point.balance.to_ether_string(),
+                point.inflow.to_ether_string(),
+                point.outflow.to_ether_string(),
+                point.fees.to_ether_string(),
+            ])?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the series to a JSON file. Amounts are decimal strings in the
+    /// native currency, so no precision is lost.
+    pub fn write_json(&self, path: &Path) -> Result<(), WalletError> {
+        let points: Vec<_> = self
+            .points
+            .iter()
+            .map(|point| {
+                json!({
+                    "date": point.date.to_string(),
+                    "balance": point.balance.to_ether_string(),
+                    "inflow": point.inflow.to_ether_string(),
+                    "outflow": point.outflow.to_ether_string(),
+                    "fees": point.fees.to_ether_string(),
+                })
+            })
+            .collect();
+
+        let document = json!({
+            "chain": self.chain.name(),
+            "address": self.address.to_string(),
+            "asset": self.chain.native_symbol(),
+            "interval": self.interval.to_string(),
+            "points": points,
+        });
+
+        fs::write(path, serde_json::to_string_pretty(&document)?)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for BalanceTimeline {
+    /// Formats the series as one line per point.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = self.chain.native_symbol();
+        writeln!(
+            f,
+            "Balance history of {} on {} ({}):",
+            self.address, self.chain, self.interval
+        )?;
+        for point in &self.points {
+            writeln!(
+                f,
+                "{} | {} {} | in {} | out {} | fees {}",
+                point.date,
+                point.balance.to_ether_string(),
+                symbol,
+                point.inflow.to_ether_string(),
+                point.outflow.to_ether_string(),
+                point.fees.to_ether_string(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Replays a wallet's history into its balance at the end of each period.
+///
+/// Incoming transfers, internal ones included, add to the balance; outgoing
+/// transfers and the fees of the transactions the wallet sent are taken
+/// from it. A failed transaction only costs its fee, since the value it
+/// tried to move never left the wallet. Periods without activity repeat
+/// the previous balance. If the history misses some credits, such as
+/// validator withdrawals, the balance is floored at zero instead of going
+/// negative.
+///
+/// # Arguments
+/// * `chain` - Chain the history belongs to.
+/// * `wallet` - Wallet the history belongs to.
+/// * `transactions` - Transactions sorted from oldest to newest.
+/// * `interval` - Spacing of the points.
+/// * `until` - Day the series ends on, usually today.
+pub fn balance_timeline(
+    chain: Chain,
+    wallet: &Address,
+    transactions: &[Transaction],
+    interval: SamplingInterval,
+    until: NaiveDate,
+) -> BalanceTimeline {
+    let mut points = Vec::new();
+
+    if let Some(first) = transactions.first() {
+        let mut pending = transactions.iter().peekable();
+        let mut balance = Wei::ZERO;
+        let mut period = interval.period_start(utc_date(first.timestamp));
+        let last_period = interval.period_start(until.max(utc_date(first.timestamp)));
+
+        while period <= last_period {
+            let next = interval.next_period(period);
+            let mut point = BalancePoint {
+                date: period,
+                balance,
+                inflow: Wei::ZERO,
+                outflow: Wei::ZERO,
+                fees: Wei::ZERO,
+            };
+
+            while let Some(tx) = pending.next_if(|tx| utc_date(tx.timestamp) < next) {
+                match tx.direction {
+                    _ if tx.is_error => {}
+                    Direction::In => point.inflow += tx.quantity,
+                    Direction::Out | Direction::ContractCreation => point.outflow += tx.quantity,
+                    Direction::SelfTransfer | Direction::Unrelated => {}
+                }
+                if !tx.internal && tx.direction.is_sent() {
+                    point.fees += tx.fee();
+                }
+            }
+
+            balance = balance + point.inflow - point.outflow - point.fees;
+            point.balance = balance;
+            points.push(point);
+            period = next;
+        }
+    }
+
+    BalanceTimeline {
+        chain,
+        address: wallet.clone(),
+        interval,
+        points,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01 00:00:00 UTC.
+    const JAN_1: u64 = 1_704_067_200;
+
+    const DAY: u64 = 86_400;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn timeline(
+        transactions: &[Transaction],
+        interval: SamplingInterval,
+        until: NaiveDate,
+    ) -> BalanceTimeline {
+        let wallet = Address::parse(Transaction::SAMPLE_WALLET).unwrap();
+        balance_timeline(Chain::Ethereum, &wallet, transactions, interval, until)
+    }
+
+    fn balances(timeline: &BalanceTimeline) -> Vec<Wei> {
+        timeline.points.iter().map(|point| point.balance).collect()
+    }
+
+    #[test]
+    fn periods_start_on_their_first_day() {
+        let wednesday = date(1, 17);
+        assert_eq!(SamplingInterval::Daily.period_start(wednesday), wednesday);
+        assert_eq!(
+            SamplingInterval::Weekly.period_start(wednesday),
+            date(1, 15)
+        );
+        assert_eq!(
+            SamplingInterval::Monthly.period_start(wednesday),
+            date(1, 1)
+        );
+        assert_eq!(
+            SamplingInterval::Weekly.next_period(date(1, 15)),
+            date(1, 22)
+        );
+        assert_eq!(
+            SamplingInterval::Monthly.next_period(date(1, 1)),
+            date(2, 1)
+        );
+    }
+
+    #[test]
+    fn empty_history_has_no_points() {
+        let timeline = timeline(&[], SamplingInterval::Daily, date(1, 5));
+        assert!(timeline.points.is_empty());
+        assert_eq!(timeline.final_balance(), Wei::ZERO);
+    }
+
+    #[test]
+    fn quiet_periods_repeat_the_balance_until_the_end() {
+        let transactions = vec![
+            Transaction::received(1000, JAN_1 + 60),
+            Transaction::sent(Direction::Out, 300, 10, JAN_1 + 2 * DAY),
+        ];
+        let timeline = timeline(&transactions, SamplingInterval::Daily, date(1, 4));
+
+        let dates: Vec<NaiveDate> = timeline.points.iter().map(|point| point.date).collect();
+        assert_eq!(dates, [date(1, 1), date(1, 2), date(1, 3), date(1, 4)]);
+        assert_eq!(
+            balances(&timeline),
+            [
+                Wei::milliether(1000),
+                Wei::milliether(1000),
+                Wei::milliether(690),
+                Wei::milliether(690)
+            ]
+        );
+        let third = &timeline.points[2];
+        assert_eq!(third.outflow, Wei::milliether(300));
+        assert_eq!(third.fees, Wei::milliether(10));
+    }
+
+    #[test]
+    fn transactions_are_grouped_by_period() {
+        let transactions = vec![
+            Transaction::received(1000, JAN_1),
+            Transaction::received(500, JAN_1 + 10 * DAY),
+            Transaction::sent(Direction::Out, 200, 0, JAN_1 + 40 * DAY),
+        ];
+        let timeline = timeline(&transactions, SamplingInterval::Monthly, date(2, 20));
+
+        assert_eq!(timeline.points.len(), 2);
+        assert_eq!(timeline.points[0].inflow, Wei::milliether(1500));
+        assert_eq!(timeline.points[1].outflow, Wei::milliether(200));
+        assert_eq!(timeline.final_balance(), Wei::milliether(1300));
+    }
+
+    #[test]
+    fn failed_transaction_only_costs_its_fee() {
+        let mut failed = Transaction::sent(Direction::Out, 400, 5, JAN_1 + 60);
+        failed.is_error = true;
+        let transactions = vec![Transaction::received(1000, JAN_1), failed];
+        let timeline = timeline(&transactions, SamplingInterval::Daily, date(1, 1));
+
+        let point = &timeline.points[0];
+        assert_eq!(point.outflow, Wei::ZERO);
+        assert_eq!(point.fees, Wei::milliether(5));
+        assert_eq!(point.balance, Wei::milliether(995));
+    }
+
+    #[test]
+    fn fee_only_transaction_costs_its_fee() {
+        let transactions = vec![
+            Transaction::received(1000, JAN_1),
+            Transaction::sent(Direction::Out, 0, 7, JAN_1 + 60),
+            Transaction::sent(Direction::SelfTransfer, 250, 3, JAN_1 + 120),
+        ];
+        let timeline = timeline(&transactions, SamplingInterval::Daily, date(1, 1));
+
+        let point = &timeline.points[0];
+        assert_eq!(point.outflow, Wei::ZERO);
+        assert_eq!(point.fees, Wei::milliether(10));
+        assert_eq!(point.balance, Wei::milliether(990));
+    }
+
+    #[test]
+    fn fees_of_received_and_internal_transactions_are_not_charged() {
+        let mut received = Transaction::received(1000, JAN_1);
+        received.gas_used = 5;
+        received.gas_price = Wei::milliether(1);
+        let mut internal = Transaction::sent(Direction::Out, 100, 5, JAN_1 + 60);
+        internal.internal = true;
+        let timeline = timeline(&[received, internal], SamplingInterval::Daily, date(1, 1));
+
+        assert_eq!(timeline.points[0].fees, Wei::ZERO);
+        assert_eq!(timeline.final_balance(), Wei::milliether(900));
+    }
+
+    #[test]
+    fn missing_credits_floor_the_balance_at_zero() {
+        let transactions = vec![
+            Transaction::received(100, JAN_1),
+            Transaction::sent(Direction::Out, 500, 0, JAN_1 + DAY),
+            Transaction::received(200, JAN_1 + 2 * DAY),
+        ];
+        let timeline = timeline(&transactions, SamplingInterval::Daily, date(1, 3));
+
+        assert_eq!(
+            balances(&timeline),
+            [Wei::milliether(100), Wei::ZERO, Wei::milliether(200)]
+        );
+    }
+}
@@ -0,0 +1,468 @@
+use std::fmt;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::{balance_history::BalanceTimeline, ethereum::chain::Chain, structs::Transaction};
+
+/// Number of columns a line chart or the longest bar spans.
+pub const CHART_WIDTH: usize = 60;
+
+/// Number of rows of a line chart.
+pub const CHART_HEIGHT: usize = 12;
+
+/// Maximum number of bars in a bar chart; older periods are left out.
+pub const MAX_BARS: usize = 36;
+
+/// Blocks of increasing height, from one eighth to a full cell.
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Blocks of increasing width, from one eighth to a full cell.
+const PARTIAL_BARS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+/// What a chart shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartKind {
+    /// Balance over time, as a sparkline and a line chart.
+    #[default]
+    Balance,
+    /// Inflow and outflow per period, as a bar chart.
+    Volume,
+    /// Distribution of transaction values, as a histogram.
+    Values,
+    /// Fees per period as a bar chart, and gas prices as a sparkline.
+    Gas,
+}
+
+impl fmt::Display for ChartKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ChartKind::Balance => "balance",
+            ChartKind::Volume => "volume",
+            ChartKind::Values => "values",
+            ChartKind::Gas => "gas",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Renders values as a single line of blocks, one per value, scaled between their minimum and maximum.
+pub fn sparkline(values: &[f64]) -> String {
+    let (min, max) = bounds(values);
+    values
+        .iter()
+        .map(|&value| {
+            let level = scale(value, min, max, LEVELS.len() - 1);
+            LEVELS[level]
+        })
+        .collect()
+}
+
+/// Renders a series as a line chart with a value axis on the left.
+///
+/// When the series has more points than `width`, consecutive points are
+/// grouped and the last of each group is drawn, which keeps the end-of-period
+/// meaning of balances.
+///
+/// # Arguments
+/// * `points` - Labelled values, oldest first.
+/// * `width` - Maximum number of columns.
+/// * `height` - Number of rows.
+pub fn line_chart(points: &[(String, f64)], width: usize, height: usize) -> String {
+    if points.is_empty() || width == 0 || height == 0 {
+        return String::new();
+    }
+
+    let sampled = downsample(points, width);
+    let values: Vec<f64> = sampled.iter().map(|(_, value)| *value).collect();
+    let (min, max) = bounds(&values);
+    let rows: Vec<usize> = values
+        .iter()
+        .map(|&value| scale(value, min, max, height - 1))
+        .collect();
+
+    let top = format!("{:.4}", max);
+    let bottom = format!("{:.4}", min);
+    let axis_width = top.len().max(bottom.len());
+
+    let mut chart = String::new();
+    for row in (0..height).rev() {
+        let label = match row {
+            row if row == height - 1 => top.as_str(),
+            0 => bottom.as_str(),
+            _ => "",
+        };
+        chart.push_str(&format!("{:>axis_width$} ┤", label));
+        for (column, &point) in rows.iter().enumerate() {
+            let previous = column.checked_sub(1).map_or(point, |index| rows[index]);
+            let cell = if row == point {
+                '•'
+            } else if row > point.min(previous) && row < point.max(previous) {
+                '│'
+            } else {
+                ' '
+            };
+            chart.push(cell);
+        }
+        chart.push('\n');
+    }
+
+    chart.push_str(&format!(
+        "{:>axis_width$} └{}\n",
+        "",
+        "─".repeat(rows.len())
+    ));
+    let first = &sampled[0].0;
+    let last = &sampled[sampled.len() - 1].0;
+    let gap = rows.len().saturating_sub(first.len() + last.len()).max(1);
+    chart.push_str(&format!(
+        "{:>axis_width$}  {}{}{}\n",
+        "",
+        first,
+        " ".repeat(gap),
+        last
+    ));
+    chart
+}
+
+/// Renders labelled values as horizontal bars, the largest spanning `width` columns.
+pub fn bar_chart(bars: &[(String, f64)], width: usize) -> String {
+    let label_width = bars
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let max = bars.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+
+    let mut chart = String::new();
+    for (label, value) in bars {
+        chart.push_str(&format!(
+            "{:<label_width$} │{} {}\n",
+            label,
+            bar(*value, max, width),
+            format_value(*value)
+        ));
+    }
+    chart
+}
+
+/// Counts values into buckets of one order of magnitude each, zeros in their own bucket.
+///
+/// # Returns
+/// * One labelled count per bucket from the smallest to the largest value,
+///   empty buckets in between included.
+pub fn histogram(values: &[f64]) -> Vec<(String, f64)> {
+    let zeros = values.iter().filter(|&&value| value == 0.0).count();
+    let exponents: Vec<i32> = values
+        .iter()
+        .filter(|&&value| value > 0.0)
+        .map(|value| value.log10().floor() as i32)
+        .collect();
+
+    let mut buckets = Vec::new();
+    if zeros > 0 {
+        buckets.push(("0".to_string(), zeros as f64));
+    }
+    if let (Some(&low), Some(&high)) = (exponents.iter().min(), exponents.iter().max()) {
+        for exponent in low..=high {
+            let count = exponents.iter().filter(|&&e| e == exponent).count();
+            buckets.push((
+                format!(
+                    "{} – {}",
+                    format_power(exponent),
+                    format_power(exponent + 1)
+                ),
+                count as f64,
+            ));
+        }
+    }
+    buckets
+}
+
+/// Renders the balance history as a sparkline followed by a line chart.
+pub fn balance_chart(timeline: &BalanceTimeline) -> String {
+    if timeline.points.is_empty() {
+        return "No transactions to chart.\n".to_string();
+    }
+
+    let points: Vec<(String, f64)> = timeline
+        .points
+        .iter()
+        .map(|point| (point.date.to_string(), point.balance.to_ether_f64()))
+        .collect();
+    let values: Vec<f64> = downsample(&points, CHART_WIDTH)
+        .iter()
+        .map(|(_, value)| *value)
+        .collect();
+
+    format!(
+        "Balance of {} on {} ({}), in {}:\n{}\n\n{}",
+        timeline.address,
+        timeline.chain,
+        timeline.interval,
+        timeline.chain.native_symbol(),
+        sparkline(&values),
+        line_chart(&points, CHART_WIDTH, CHART_HEIGHT)
+    )
+}
+
+/// Renders the inflow and outflow of each period as pairs of bars.
+pub fn volume_chart(timeline: &BalanceTimeline) -> String {
+    if timeline.points.is_empty() {
+        return "No transactions to chart.\n".to_string();
+    }
+
+    let points = last_periods(&timeline.points);
+    let mut bars = Vec::new();
+    for point in points {
+        bars.push((format!("{} in", point.date), point.inflow.to_ether_f64()));
+        bars.push((format!("{} out", point.date), point.outflow.to_ether_f64()));
+    }
+
+    format!(
+        "Volume of {} on {} ({}), in {}:\n{}",
+        timeline.address,
+        timeline.chain,
+        timeline.interval,
+        timeline.chain.native_symbol(),
+        bar_chart(&bars, CHART_WIDTH)
+    )
+}
+
+/// Renders the fees paid in each period as bars, followed by a sparkline of
+/// the gas price of every transaction the wallet sent.
+pub fn gas_chart(timeline: &BalanceTimeline, transactions: &[Transaction]) -> String {
+    if timeline.points.is_empty() {
+        return "No transactions to chart.\n".to_string();
+    }
+
+    let bars: Vec<(String, f64)> = last_periods(&timeline.points)
+        .iter()
+        .map(|point| (point.date.to_string(), point.fees.to_ether_f64()))
+        .collect();
+    let gas_prices: Vec<f64> = transactions
+        .iter()
+        .filter(|tx| !tx.internal && tx.direction.is_sent())
+        .map(|tx| tx.gas_price.to_gwei_string().parse().unwrap_or(0.0))
+        .collect();
+
+    let mut chart = format!(
+        "Fees of {} on {} ({}), in {}:\n{}",
+        timeline.address,
+        timeline.chain,
+        timeline.interval,
+        timeline.chain.native_symbol(),
+        bar_chart(&bars, CHART_WIDTH)
+    );
+    if !gas_prices.is_empty() {
+        let (min, max) = bounds(&gas_prices);
+        let sampled: Vec<f64> = downsample(&gas_prices, CHART_WIDTH)
+            .into_iter()
+            .copied()
+            .collect();
+        chart.push_str(&format!(
+            "\nGas price of sent transactions, {} to {} gwei:\n{}\n",
+            format_value(min),
+            format_value(max),
+            sparkline(&sampled)
+        ));
+    }
+    chart
+}
+
+/// Renders the distribution of the values of the wallet's transactions.
+pub fn values_chart(chain: Chain, transactions: &[Transaction]) -> String {
+    if transactions.is_empty() {
+        return "No transactions to chart.\n".to_string();
+    }
+
+    let values: Vec<f64> = transactions
+        .iter()
+        .map(|tx| tx.quantity.to_ether_f64())
+        .collect();
+
+    format!(
+        "Transaction values on {}, in {}:\n{}",
+        chain,
+        chain.native_symbol(),
+        bar_chart(&histogram(&values), CHART_WIDTH)
+    )
+}
+
+/// Keeps at most `width` items by splitting them into groups of consecutive
+/// items and taking the last of each group.
+fn downsample<T>(items: &[T], width: usize) -> Vec<&T> {
+    let group = items.len().div_ceil(width.max(1)).max(1);
+    items
+        .chunks(group)
+        .filter_map(|chunk| chunk.last())
+        .collect()
+}
+
+/// Returns the last [`MAX_BARS`] items.
+fn last_periods<T>(items: &[T]) -> &[T] {
+    &items[items.len().saturating_sub(MAX_BARS)..]
+}
+
+/// Returns the minimum and maximum of `values`, both zero when empty.
+fn bounds(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    (min, max)
+}
+
+/// Maps `value` from `min..=max` to `0..=steps`, a flat series mapping to the middle.
+fn scale(value: f64, min: f64, max: f64, steps: usize) -> usize {
+    if max <= min {
+        return steps / 2;
+    }
+    let ratio = (value - min) / (max - min);
+    ((ratio * steps as f64).round() as usize).min(steps)
+}
+
+/// Renders a bar of `value` relative to `max`, with eighth-of-a-cell precision.
+fn bar(value: f64, max: f64, width: usize) -> String {
+    if max <= 0.0 || value <= 0.0 {
+        return String::new();
+    }
+    let eighths = ((value / max) * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    let remainder = eighths % 8;
+    if remainder > 0 {
+        bar.push(PARTIAL_BARS[remainder - 1]);
+    }
+    bar
+}
+
+/// Formats a chart value with up to four decimals, without trailing zeros.
+fn format_value(value: f64) -> String {
+    let formatted = format!("{:.4}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "" | "-0" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Formats a power of ten as a plain decimal number.
+fn format_power(exponent: i32) -> String {
+    if exponent >= 0 {
+        format!("{}", 10f64.powi(exponent))
+    } else {
+        format!("{:.*}", (-exponent) as usize, 10f64.powi(exponent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_maps_to_steps() {
+        assert_eq!(scale(0.0, 0.0, 10.0, 7), 0);
+        assert_eq!(scale(10.0, 0.0, 10.0, 7), 7);
+        assert_eq!(scale(5.0, 0.0, 10.0, 8), 4);
+        assert_eq!(scale(20.0, 0.0, 10.0, 7), 7);
+        // A flat series sits in the middle.
+        assert_eq!(scale(3.0, 3.0, 3.0, 8), 4);
+    }
+
+    #[test]
+    fn bounds_of_nothing_are_zero() {
+        assert_eq!(bounds(&[]), (0.0, 0.0));
+        assert_eq!(bounds(&[2.0, -1.0, 5.0]), (-1.0, 5.0));
+    }
+
+    #[test]
+    fn sparkline_has_one_block_per_value() {
+        assert_eq!(sparkline(&[0.0, 1.0, 2.0]), "▁▅█");
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(sparkline(&[1.0, 1.0]), "▄▄");
+    }
+
+    #[test]
+    fn bar_uses_eighths_of_a_cell() {
+        assert_eq!(bar(10.0, 10.0, 4), "████");
+        assert_eq!(bar(5.0, 10.0, 4), "██");
+        assert_eq!(bar(1.0, 4.0, 2), "▌");
+        assert_eq!(bar(3.0, 8.0, 1), "▍");
+        assert_eq!(bar(0.0, 10.0, 4), "");
+        assert_eq!(bar(5.0, 0.0, 4), "");
+    }
+
+    #[test]
+    fn bar_chart_aligns_labels() {
+        let bars = [("a".to_string(), 2.0), ("long".to_string(), 1.0)];
+        assert_eq!(bar_chart(&bars, 2), "a    │██ 2\nlong │█ 1\n");
+    }
+
+    #[test]
+    fn histogram_buckets_by_order_of_magnitude() {
+        let buckets = histogram(&[0.0, 0.05, 0.5, 0.7, 30.0]);
+        let expected = [
+            ("0", 1.0),
+            ("0.01 – 0.1", 1.0),
+            ("0.1 – 1", 2.0),
+            ("1 – 10", 0.0),
+            ("10 – 100", 1.0),
+        ];
+
+        assert_eq!(buckets.len(), expected.len());
+        for ((label, count), (expected_label, expected_count)) in buckets.iter().zip(expected) {
+            assert_eq!(label, expected_label);
+            assert_eq!(*count, expected_count);
+        }
+        assert!(histogram(&[]).is_empty());
+    }
+
+    #[test]
+    fn downsample_keeps_the_last_of_each_group() {
+        let items: Vec<u32> = (1..=10).collect();
+        assert_eq!(downsample(&items, 5), [&2, &4, &6, &8, &10]);
+        assert_eq!(downsample(&items, 4), [&3, &6, &9, &10]);
+        assert_eq!(downsample(&items, 20).len(), 10);
+        assert_eq!(downsample(&items, 0), [&10]);
+        assert!(downsample::<u32>(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn last_periods_keeps_the_newest() {
+        let items: Vec<usize> = (0..MAX_BARS + 4).collect();
+        assert_eq!(last_periods(&items).len(), MAX_BARS);
+        assert_eq!(last_periods(&items)[0], 4);
+        assert_eq!(last_periods(&items[..3]), [0, 1, 2]);
+    }
+
+    #[test]
+    fn formats_values_without_trailing_zeros() {
+        assert_eq!(format_value(1.5), "1.5");
+        assert_eq!(format_value(2.0), "2");
+        assert_eq!(format_value(0.00001), "0");
+        assert_eq!(format_value(-0.00001), "0");
+        assert_eq!(format_value(0.12345), "0.1235");
+        assert_eq!(format_power(2), "100");
+        assert_eq!(format_power(-3), "0.001");
+    }
+
+    #[test]
+    fn line_chart_connects_points() {
+        let points: Vec<(String, f64)> = [0.0, 2.0, 1.0]
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (format!("d{}", index), *value))
+            .collect();
+        let chart = line_chart(&points, 10, 3);
+        let lines: Vec<&str> = chart.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "2.0000 ┤ • ");
+        assert_eq!(lines[1], "       ┤ │•");
+        assert_eq!(lines[2], "0.0000 ┤•  ");
+        assert!(lines[4].contains("d0") && lines[4].ends_with("d2"));
+        assert_eq!(line_chart(&[], 10, 3), "");
+    }
+}
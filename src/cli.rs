@@ -0,0 +1,219 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::{
+    address::Address,
+    balance_history::SamplingInterval,
+    charts::ChartKind,
+    cost_basis::CostBasisMethod,
+    currency::Currency,
+    dashboard::{DEFAULT_DASHBOARD_LIMIT, DEFAULT_REFRESH_SECS},
+    enums::CounterpartySort,
+    ethereum::chain::Chain,
+    prices::PriceSource,
+    tax::ExportFormat,
+};
+
+/// Number of transactions fetched by the subcommands when `--limit` is omitted.
+const DEFAULT_TRANSACTION_LIMIT: i32 = 10;
+
+/// Seconds between two polls of watch mode when `--interval` is omitted.
+const DEFAULT_WATCH_INTERVAL_SECS: u64 = 30;
+
+/// Number of counterparties listed when `--limit` is omitted, and in the interactive menu.
+pub const DEFAULT_COUNTERPARTY_LIMIT: usize = 10;
+
+/// Command-line arguments of the wallet tracker.
+///
+/// When no subcommand is given the interactive menu is started instead.
+#[derive(Debug, Parser)]
+#[command(
+    name = "wallet-tracker",
+    version,
+    about = "Track Ethereum wallet balances, transactions and statistics"
+)]
+pub struct Cli {
+    /// Chain the wallet is queried on. Defaults to `chain` in the config file, then Ethereum.
+    #[arg(long, global = true, value_enum)]
+    pub chain: Option<Chain>,
+
+    /// JSON-RPC endpoint for the selected chain. Overrides the environment and the config file.
+    #[arg(long, global = true)]
+    pub rpc_url: Option<String>,
+
+    /// SQLite file used as transaction cache. Overrides `WALLET_TRACKER_DB` and the config file.
+    #[arg(long, global = true)]
+    pub db: Option<PathBuf>,
+
+    /// ERC-20 token contract to include in the token view. Can be repeated.
+    #[arg(long = "token", global = true)]
+    pub tokens: Vec<Address>,
+
+    /// Fiat currency (ISO 4217 code) to show values in. Can be repeated.
+    #[arg(long = "currency", global = true)]
+    pub currencies: Vec<Currency>,
+
+    /// Lot matching method for gains. Defaults to `cost_basis_method` in the config file, then FIFO.
+    #[arg(long, global = true, value_enum)]
+    pub cost_basis_method: Option<CostBasisMethod>,
+
+    /// Price source to ask for fiat prices, in priority order. Can be repeated.
+    #[arg(long = "price-source", global = true, value_enum)]
+    pub price_sources: Vec<PriceSource>,
+
+    /// CSV file of fixed prices (`asset,currency,price`) for the `static` price source.
+    #[arg(long, global = true)]
+    pub price_file: Option<PathBuf>,
+
+    /// CSV file of daily prices (`date,asset,currency,price`) for the `history` price source.
+    #[arg(long, global = true)]
+    pub price_history_file: Option<PathBuf>,
+
+    /// Path of the TOML config file. Defaults to `wallet-tracker.toml` if present.
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Non-interactive subcommands, one for each [`Action`](crate::enums::Action).
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Display the wallet's balance in Ether.
+    Balance {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+    },
+    /// Display the wallet's balance in the configured fiat currencies.
+    Fiat {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+    },
+    /// Display the wallet's ERC-20 token balances next to its native balance.
+    Tokens {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+    },
+    /// Display the NFTs held by the wallet and how it acquired them.
+    Nfts {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+    },
+    /// Show recent transactions for the wallet.
+    Transactions {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+        /// Number of most recent transactions to fetch.
+        #[arg(long, default_value_t = DEFAULT_TRANSACTION_LIMIT, value_parser = clap::value_parser!(i32).range(1..))]
+        limit: i32,
+    },
+    /// Show the total and average fees paid in recent transactions.
+    Gas {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+        /// Number of most recent transactions to include.
+        #[arg(long, default_value_t = DEFAULT_TRANSACTION_LIMIT, value_parser = clap::value_parser!(i32).range(1..))]
+        limit: i32,
+    },
+    /// Display calculated statistics for the wallet.
+    Stats {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+    },
+    /// Display realized and unrealized gains of the native currency.
+    CostBasis {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+    },
+    /// Show the addresses the wallet transacts with the most.
+    Counterparties {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+        /// Order of the counterparties, largest first.
+        #[arg(long, value_enum, default_value_t)]
+        sort: CounterpartySort,
+        /// Number of counterparties to include.
+        #[arg(long, default_value_t = DEFAULT_COUNTERPARTY_LIMIT)]
+        limit: usize,
+        /// CSV file the counterparties are written to instead of being displayed.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Show the wallet's balance over time, or export it with `--out`.
+    BalanceHistory {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+        /// Spacing of the points.
+        #[arg(long, value_enum, default_value_t)]
+        interval: SamplingInterval,
+        /// File the series is written to instead of being displayed: JSON if it ends in `.json`, CSV otherwise.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Draw a chart of the wallet's history in the terminal.
+    Chart {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+        /// Chart to draw.
+        #[arg(long, value_enum, default_value_t)]
+        kind: ChartKind,
+        /// Spacing of the points of the balance, volume and gas charts.
+        #[arg(long, value_enum, default_value_t)]
+        interval: SamplingInterval,
+    },
+    /// Open a full-screen dashboard that refreshes in the background.
+    Dashboard {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+        /// Number of recent transactions to list.
+        #[arg(long, default_value_t = DEFAULT_DASHBOARD_LIMIT)]
+        limit: usize,
+        /// Seconds between two refreshes.
+        #[arg(long, default_value_t = DEFAULT_REFRESH_SECS, value_parser = clap::value_parser!(u64).range(1..))]
+        refresh: u64,
+    },
+    /// Poll the chain and print new transactions of one or more wallets until Ctrl-C.
+    Watch {
+        /// Wallets to watch, separated by commas or spaces.
+        #[arg(env = "WALLET_ADDRESS", required = true, num_args = 1.., value_delimiter = ',')]
+        wallets: Vec<Address>,
+        /// Seconds between two polls.
+        #[arg(long, default_value_t = DEFAULT_WATCH_INTERVAL_SECS, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+    },
+    /// Export wallet statistics and transactions to CSV files.
+    Export {
+        #[arg(env = "WALLET_ADDRESS")]
+        wallet: Address,
+        /// Directory the CSV files are written to.
+        #[arg(long, default_value = ".")]
+        out: PathBuf,
+        /// Layout of the exported files: the tracker's own or a tax tool's import format.
+        #[arg(long, value_enum, default_value_t)]
+        format: ExportFormat,
+    },
+}
+
+impl Command {
+    /// Returns the wallet address the subcommand operates on.
+    pub fn wallet(&self) -> &Address {
+        match self {
+            Command::Balance { wallet }
+            | Command::Fiat { wallet }
+            | Command::Tokens { wallet }
+            | Command::Nfts { wallet }
+            | Command::Transactions { wallet, .. }
+            | Command::Gas { wallet, .. }
+            | Command::Stats { wallet }
+            | Command::CostBasis { wallet }
+            | Command::Counterparties { wallet, .. }
+            | Command::BalanceHistory { wallet, .. }
+            | Command::Chart { wallet, .. }
+            | Command::Dashboard { wallet, .. }
+            | Command::Export { wallet, .. } => wallet,
+            Command::Watch { wallets, .. } => &wallets[0],
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use dotenv::dotenv;
+use serde::Deserialize;
+
+use crate::{
+    address::Address,
+    cli::Cli,
+    cost_basis::CostBasisMethod,
+    currency::Currency,
+    errors::WalletError,
+    ethereum::chain::Chain,
+    prices::{DEFAULT_PRICE_TTL, PriceSource},
+};
+
+/// Transaction cache used when no database path is configured.
+pub const DEFAULT_DB_PATH: &str = "wallet-tracker.db";
+
+/// Config file looked up in the working directory when `--config` is not given.
+const DEFAULT_CONFIG_FILE: &str = "wallet-tracker.toml";
+
+/// Environment variable overriding the transaction cache location.
+const DB_PATH_ENV: &str = "WALLET_TRACKER_DB";
+
+/// Settings read from the TOML config file. Every field is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    /// Chain queried when `--chain` is not given.
+    pub chain: Option<Chain>,
+    /// JSON-RPC endpoint of the Ethereum mainnet node.
+    pub rpc_url: Option<String>,
+    /// JSON-RPC endpoints keyed by chain name, e.g. `arbitrum = "..."`.
+    #[serde(default)]
+    pub rpc_urls: HashMap<Chain, String>,
+    /// Location of the SQLite transaction cache.
+    pub db_path: Option<PathBuf>,
+    /// ERC-20 token contracts to always show, keyed by chain name.
+    #[serde(default)]
+    pub tokens: HashMap<Chain, Vec<Address>>,
+    /// Fiat currencies to show values in, e.g. `["EUR", "CHF"]`.
+    pub currencies: Option<Vec<Currency>>,
+    /// Lot matching method used for gains.
+    pub cost_basis_method: Option<CostBasisMethod>,
+    /// Price sources in priority order.
+    pub price_sources: Option<Vec<PriceSource>>,
+    /// CSV file of fixed prices used by the `static` price source.
+    pub price_file: Option<PathBuf>,
+    /// CSV file of daily prices used by the `history` price source.
+    pub price_history_file: Option<PathBuf>,
+    /// Seconds a fetched price is reused before asking the sources again.
+    pub price_ttl: Option<u64>,
+}
+
+/// Resolved runtime configuration.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Chain the tracked wallet is queried on.
+    pub chain: Chain,
+    /// SQLite file holding the transaction cache.
+    pub db_path: PathBuf,
+    /// ERC-20 token contracts to show on the selected chain.
+    pub tokens: Vec<Address>,
+    /// Fiat currencies values are shown in, in display order.
+    pub currencies: Vec<Currency>,
+    /// Lot matching method used for gains.
+    pub cost_basis_method: CostBasisMethod,
+    /// Price sources asked in order until one answers.
+    pub price_sources: Vec<PriceSource>,
+    /// CSV file of fixed prices used by the `static` price source.
+    pub price_file: Option<PathBuf>,
+    /// CSV file of daily prices used by the `history` price source.
+    pub price_history_file: Option<PathBuf>,
+    /// How long a fetched price is reused.
+    pub price_ttl: Duration,
+    /// Endpoint passed with `--rpc-url`, applied to the selected chain only.
+    rpc_url_override: Option<String>,
+    /// Endpoints from the config file.
+    file_rpc_urls: HashMap<Chain, String>,
+}
+
+impl Config {
+    /// Builds the configuration from the CLI flags, the environment and the config file.
+    ///
+    /// Values are resolved in that order of precedence, falling back to
+    /// Ethereum mainnet and [`DEFAULT_DB_PATH`] when nothing is set.
+    ///
+    /// Token contracts passed with `--token` are added to the configured ones.
+    /// Fiat values are shown in USD unless other currencies are configured.
+    /// Without configured price sources, Coinbase, CoinGecko and Kraken are
+    /// asked in that order, followed by the price history file and the price
+    /// file when they are set.
+    ///
+    /// # Arguments
+    /// * `cli` - Parsed command-line arguments.
+    ///
+    /// # Errors
+    /// Returns an error if an explicitly given config file is missing or if
+    /// the config file is not valid TOML.
+    pub fn load(cli: &Cli) -> Result<Config, WalletError> {
+        dotenv().ok();
+
+        let mut file_config = match &cli.config {
+            Some(path) => read_config_file(path)?,
+            None => {
+                let path = Path::new(DEFAULT_CONFIG_FILE);
+                if path.exists() {
+                    read_config_file(path)?
+                } else {
+                    FileConfig::default()
+                }
+            }
+        };
+
+        let chain = cli.chain.or(file_config.chain).unwrap_or_default();
+
+        if let Some(mainnet_url) = file_config.rpc_url {
+            file_config
+                .rpc_urls
+                .entry(Chain::Ethereum)
+                .or_insert(mainnet_url);
+        }
+
+        let db_path = cli
+            .db
+            .clone()
+            .or_else(|| std::env::var_os(DB_PATH_ENV).map(PathBuf::from))
+            .or(file_config.db_path)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DB_PATH));
+
+        let mut tokens = cli.tokens.clone();
+        tokens.extend(file_config.tokens.remove(&chain).unwrap_or_default());
+
+        let currencies = if !cli.currencies.is_empty() {
+            cli.currencies.clone()
+        } else {
+            file_config
+                .currencies
+                .filter(|currencies| !currencies.is_empty())
+                .unwrap_or_else(|| vec![Currency::default()])
+        };
+
+        let cost_basis_method = cli
+            .cost_basis_method
+            .or(file_config.cost_basis_method)
+            .unwrap_or_default();
+
+        let price_file = cli.price_file.clone().or(file_config.price_file);
+        let price_history_file = cli
+            .price_history_file
+            .clone()
+            .or(file_config.price_history_file);
+        let price_sources = if !cli.price_sources.is_empty() {
+            cli.price_sources.clone()
+        } else if let Some(sources) = file_config.price_sources {
+            sources
+        } else {
+            let mut sources = vec![
+                PriceSource::Coinbase,
+                PriceSource::Coingecko,
+                PriceSource::Kraken,
+            ];
+            if price_history_file.is_some() {
+                sources.push(PriceSource::History);
+            }
+            if price_file.is_some() {
+                sources.push(PriceSource::Static);
+            }
+            sources
+        };
+        let price_ttl = file_config
+            .price_ttl
+            .map_or(DEFAULT_PRICE_TTL, Duration::from_secs);
+
+        Ok(Config {
+            chain,
+            db_path,
+            tokens,
+            currencies,
+            cost_basis_method,
+            price_sources,
+            price_file,
+            price_history_file,
+            price_ttl,
+            rpc_url_override: cli.rpc_url.clone(),
+            file_rpc_urls: file_config.rpc_urls,
+        })
+    }
+
+    /// Returns the JSON-RPC endpoint for `chain`.
+    ///
+    /// `--rpc-url` wins for the selected chain, then the chain's environment
+    /// variable (see [`Chain::rpc_url_env`]), then the config file, then the
+    /// chain's public default.
+    pub fn rpc_url(&self, chain: Chain) -> String {
+        self.rpc_url_override
+            .clone()
+            .filter(|_| chain == self.chain)
+            .or_else(|| std::env::var(chain.rpc_url_env()).ok())
+            .or_else(|| self.file_rpc_urls.get(&chain).cloned())
+            .unwrap_or_else(|| chain.default_rpc_url().to_string())
+    }
+}
+
+/// Reads and parses a TOML config file.
+fn read_config_file(path: &Path) -> Result<FileConfig, WalletError> {
+    let contents = fs::read_to_string(path)?;
+    Ok(toml::from_str(&contents)?)
+}
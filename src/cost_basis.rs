@@ -0,0 +1,432 @@
+use std::fmt;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::{
+    amount::Wei,
+    currency::{Currency, FiatAmount},
+    enums::Direction,
+    structs::Transaction,
+};
+
+/// How disposals are matched against previously acquired lots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CostBasisMethod {
+    /// First in, first out: the oldest lots are sold first.
+    #[default]
+    Fifo,
+    /// Last in, first out: the newest lots are sold first.
+    Lifo,
+    /// Highest in, first out: the most expensive lots are sold first.
+    Hifo,
+    /// Average cost: every unit costs the running average of all holdings.
+    Average,
+}
+
+impl fmt::Display for CostBasisMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            CostBasisMethod::Fifo => "FIFO",
+            CostBasisMethod::Lifo => "LIFO",
+            CostBasisMethod::Hifo => "HIFO",
+            CostBasisMethod::Average => "average cost",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// A quantity of the native currency acquired in one transaction.
+#[derive(Debug, Clone)]
+struct Lot {
+    quantity: Wei,
+    unit_cost: f64,
+}
+
+/// Sale or transfer out of native currency, matched against acquired lots.
+///
+/// The fee of every transaction the wallet sent is a disposal of its own,
+/// with no proceeds: the currency spent on gas is gone, so its cost is a
+/// realized loss.
+#[derive(Debug)]
+pub struct Disposal {
+    /// Hash of the disposing transaction.
+    pub hash: String,
+    /// Date of the disposal.
+    pub date: String,
+    /// Quantity disposed of.
+    pub quantity: Wei,
+    /// Fiat value of the disposed quantity at the time of the disposal.
+    pub proceeds: f64,
+    /// Fiat cost of the lots consumed by the disposal.
+    pub cost_basis: f64,
+    /// Quantity that could not be matched to any known acquisition; its cost is taken as zero.
+    pub unmatched: Wei,
+    /// Whether the disposal is the fee of the transaction rather than its value.
+    pub fee: bool,
+}
+
+impl Disposal {
+    /// Returns the realized gain, negative for a loss.
+    pub fn gain(&self) -> f64 {
+        self.proceeds - self.cost_basis
+    }
+}
+
+/// Realized and unrealized gains of a wallet's native currency.
+#[derive(Debug)]
+pub struct CostBasisReport {
+    /// Lot matching method used.
+    pub method: CostBasisMethod,
+    /// Currency every fiat amount is expressed in.
+    pub currency: Currency,
+    /// Disposals, oldest first.
+    pub disposals: Vec<Disposal>,
+    /// Quantity left in open lots after replaying the history. It differs
+    /// from the balance when the history misses some movements, such as
+    /// validator withdrawals.
+    pub open_quantity: Wei,
+    /// Fiat cost of the open lots, taken as the cost basis of the balance.
+    pub open_cost: f64,
+    /// Transactions that moved value but had no historical price.
+    pub unpriced: usize,
+    /// Current balance, as returned by `get_balance`.
+    pub balance: Wei,
+    /// Fiat value of the current balance at the spot price.
+    pub market_value: f64,
+}
+
+impl CostBasisReport {
+    /// Returns the sum of the realized gains of every disposal.
+    pub fn realized_gain(&self) -> f64 {
+        self.disposals.iter().map(Disposal::gain).sum()
+    }
+
+    /// Returns the unrealized gain of the current balance at the spot price.
+    pub fn unrealized_gain(&self) -> f64 {
+        self.market_value - self.open_cost
+    }
+
+    /// Returns a fiat amount in the report currency.
+    pub fn fiat(&self, amount: f64) -> FiatAmount {
+        FiatAmount {
+            currency: self.currency.clone(),
+            amount,
+        }
+    }
+}
+
+impl fmt::Display for CostBasisReport {
+    /// Formats the gains summary in a human-readable multiline format.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cost basis method: {}\n\
+             Disposals: {}\n\
+             Realized gain: {}\n\
+             Balance: {}\n\
+             Open lots: {}\n\
+             Cost basis of open lots: {}\n\
+             Market value: {}\n\
+             Unrealized gain: {}\n",
+            self.method,
+            self.disposals.len(),
+            self.fiat(self.realized_gain()),
+            self.balance.to_ether_string(),
+            self.open_quantity.to_ether_string(),
+            self.fiat(self.open_cost),
+            self.fiat(self.market_value),
+            self.fiat(self.unrealized_gain()),
+        )
+    }
+}
+
+/// Replays a wallet's native-currency history into lots and disposals.
+///
+/// Every incoming transfer, internal ones included, opens a lot at the price
+/// of its day; every outgoing transfer or contract deployment carrying value
+/// is a disposal matched against the open lots with `method`, and so is the
+/// fee of every transaction the wallet sent. Self-transfers move no value
+/// and failed transactions only pay their fee. Prices come from the
+/// `currency` entry of each transaction's fiat values, so the transactions
+/// must have been valued first.
+///
+/// # Arguments
+/// * `method` - Lot matching method.
+/// * `transactions` - Valued transactions, oldest first.
+/// * `currency` - Currency the report is expressed in.
+/// * `balance` - Current balance.
+/// * `spot_price` - Current price of one unit of the native currency.
+pub fn compute_cost_basis(
+    method: CostBasisMethod,
+    transactions: &[Transaction],
+    currency: &Currency,
+    balance: Wei,
+    spot_price: f64,
+) -> CostBasisReport {
+    let mut lots: Vec<Lot> = Vec::new();
+    let mut disposals = Vec::new();
+    let mut unpriced = 0;
+
+    for tx in transactions {
+        if tx.quantity != Wei::ZERO && !tx.is_error {
+            let value = tx
+                .fiat_values
+                .iter()
+                .find(|value| &value.currency == currency)
+                .map(|value| value.amount);
+            if value.is_none() {
+                unpriced += 1;
+            }
+            let value = value.unwrap_or_default();
+
+            match tx.direction {
+                Direction::In => lots.push(Lot {
+                    quantity: tx.quantity,
+                    unit_cost: value / tx.quantity.to_ether_f64(),
+                }),
+                Direction::Out | Direction::ContractCreation => {
+                    let (cost_basis, unmatched) = consume_lots(&mut lots, method, tx.quantity);
+                    disposals.push(Disposal {
+                        hash: tx.hash.clone(),
+                        date: tx.date.clone(),
+                        quantity: tx.quantity,
+                        proceeds: value,
+                        cost_basis,
+                        unmatched,
+                        fee: false,
+                    });
+                }
+                Direction::SelfTransfer | Direction::Unrelated => {}
+            }
+        }
+
+        // The sender pays the fee, whether the transaction succeeded or not.
+        let fee = tx.fee();
+        if !tx.internal && tx.direction.is_sent() && fee != Wei::ZERO {
+            let (cost_basis, unmatched) = consume_lots(&mut lots, method, fee);
+            disposals.push(Disposal {
+                hash: tx.hash.clone(),
+                date: tx.date.clone(),
+                quantity: fee,
+                proceeds: 0.0,
+                cost_basis,
+                unmatched,
+                fee: true,
+            });
+        }
+    }
+
+    CostBasisReport {
+        method,
+        currency: currency.clone(),
+        disposals,
+        open_quantity: lots.iter().map(|lot| lot.quantity).sum(),
+        open_cost: lots
+            .iter()
+            .map(|lot| lot.unit_cost * lot.quantity.to_ether_f64())
+            .sum(),
+        unpriced,
+        balance,
+        market_value: spot_price * balance.to_ether_f64(),
+    }
+}
+
+/// Removes `quantity` from the open lots in the order given by `method`.
+///
+/// # Returns
+/// * The fiat cost of the consumed quantity and the quantity no lot covered.
+fn consume_lots(lots: &mut Vec<Lot>, method: CostBasisMethod, quantity: Wei) -> (f64, Wei) {
+    if method == CostBasisMethod::Average {
+        // Pool every lot into a single one at the average unit cost.
+        let total: Wei = lots.iter().map(|lot| lot.quantity).sum();
+        let cost: f64 = lots
+            .iter()
+            .map(|lot| lot.unit_cost * lot.quantity.to_ether_f64())
+            .sum();
+        lots.clear();
+        if total != Wei::ZERO {
+            lots.push(Lot {
+                quantity: total,
+                unit_cost: cost / total.to_ether_f64(),
+            });
+        }
+    }
+
+    let mut remaining = quantity;
+    let mut cost_basis = 0.0;
+
+    while remaining != Wei::ZERO && !lots.is_empty() {
+        let index = match method {
+            CostBasisMethod::Fifo | CostBasisMethod::Average => 0,
+            CostBasisMethod::Lifo => lots.len() - 1,
+            CostBasisMethod::Hifo => lots
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.unit_cost.total_cmp(&b.unit_cost))
+                .map_or(0, |(index, _)| index),
+        };
+
+        let lot = &mut lots[index];
+        let consumed = remaining.min(lot.quantity);
+        cost_basis += lot.unit_cost * consumed.to_ether_f64();
+        lot.quantity = lot.quantity - consumed;
+        remaining = remaining - consumed;
+
+        if lot.quantity == Wei::ZERO {
+            lots.remove(index);
+        }
+    }
+
+    (cost_basis, remaining)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a transaction of `milli` thousandths of Ether valued at `price` per Ether.
+    fn priced(direction: Direction, milli: u64, price: f64, timestamp: u64) -> Transaction {
+        let mut tx = Transaction::sample(direction, Wei::milliether(milli), timestamp);
+        tx.fiat_values.push(FiatAmount {
+            currency: Currency::default(),
+            amount: price * milli as f64 / 1000.0,
+        });
+        tx
+    }
+
+    /// Three purchases of one Ether at 100, 400 and 200, then a sale of one Ether at 250.
+    fn history() -> Vec<Transaction> {
+        vec![
+            priced(Direction::In, 1000, 100.0, 1),
+            priced(Direction::In, 1000, 400.0, 2),
+            priced(Direction::In, 1000, 200.0, 3),
+            priced(Direction::Out, 1000, 250.0, 4),
+        ]
+    }
+
+    fn report(method: CostBasisMethod, transactions: &[Transaction]) -> CostBasisReport {
+        compute_cost_basis(
+            method,
+            transactions,
+            &Currency::default(),
+            Wei::milliether(2000),
+            300.0,
+        )
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn methods_consume_different_lots() {
+        for (method, cost, open_cost) in [
+            (CostBasisMethod::Fifo, 100.0, 600.0),
+            (CostBasisMethod::Lifo, 200.0, 500.0),
+            (CostBasisMethod::Hifo, 400.0, 300.0),
+            (CostBasisMethod::Average, 700.0 / 3.0, 1400.0 / 3.0),
+        ] {
+            let report = report(method, &history());
+            assert_eq!(report.disposals.len(), 1, "{}", method);
+            let disposal = &report.disposals[0];
+            assert_close(disposal.proceeds, 250.0);
+            assert_close(disposal.cost_basis, cost);
+            assert_close(disposal.gain(), 250.0 - cost);
+            assert_eq!(disposal.unmatched, Wei::ZERO);
+            assert_eq!(report.open_quantity, Wei::milliether(2000));
+            assert_close(report.open_cost, open_cost);
+            assert_close(report.unrealized_gain(), 600.0 - open_cost);
+        }
+    }
+
+    #[test]
+    fn disposal_spans_several_lots() {
+        let transactions = vec![
+            priced(Direction::In, 1000, 100.0, 1),
+            priced(Direction::In, 1000, 400.0, 2),
+            priced(Direction::Out, 1500, 300.0, 3),
+        ];
+        let report = report(CostBasisMethod::Fifo, &transactions);
+
+        assert_close(report.disposals[0].cost_basis, 100.0 + 200.0);
+        assert_eq!(report.open_quantity, Wei::milliether(500));
+        assert_close(report.open_cost, 200.0);
+    }
+
+    #[test]
+    fn disposal_beyond_lots_is_unmatched() {
+        let transactions = vec![
+            priced(Direction::In, 1000, 100.0, 1),
+            priced(Direction::Out, 3000, 300.0, 2),
+        ];
+        let report = report(CostBasisMethod::Fifo, &transactions);
+
+        let disposal = &report.disposals[0];
+        assert_close(disposal.cost_basis, 100.0);
+        assert_eq!(disposal.unmatched, Wei::milliether(2000));
+        assert_eq!(report.open_quantity, Wei::ZERO);
+    }
+
+    #[test]
+    fn fees_are_disposed_of_without_proceeds() {
+        let mut sale = priced(Direction::Out, 500, 200.0, 2);
+        sale.gas_used = 10;
+        sale.gas_price = Wei::milliether(1);
+        let transactions = vec![priced(Direction::In, 1000, 100.0, 1), sale];
+        let report = report(CostBasisMethod::Fifo, &transactions);
+
+        assert_eq!(report.disposals.len(), 2);
+        let fee = &report.disposals[1];
+        assert!(fee.fee);
+        assert_eq!(fee.quantity, Wei::milliether(10));
+        assert_close(fee.proceeds, 0.0);
+        assert_close(fee.cost_basis, 1.0);
+        assert_close(fee.gain(), -1.0);
+        assert_eq!(report.open_quantity, Wei::milliether(490));
+    }
+
+    #[test]
+    fn failed_transaction_only_disposes_of_its_fee() {
+        let mut failed = priced(Direction::Out, 500, 200.0, 2);
+        failed.is_error = true;
+        failed.gas_used = 10;
+        failed.gas_price = Wei::milliether(1);
+        let transactions = vec![priced(Direction::In, 1000, 100.0, 1), failed];
+        let report = report(CostBasisMethod::Fifo, &transactions);
+
+        assert_eq!(report.disposals.len(), 1);
+        assert!(report.disposals[0].fee);
+        assert_eq!(report.open_quantity, Wei::milliether(990));
+    }
+
+    #[test]
+    fn received_fee_is_not_a_disposal() {
+        let mut received = priced(Direction::In, 1000, 100.0, 1);
+        received.gas_used = 10;
+        received.gas_price = Wei::milliether(1);
+        let report = report(CostBasisMethod::Fifo, &[received]);
+
+        assert!(report.disposals.is_empty());
+        assert_eq!(report.open_quantity, Wei::milliether(1000));
+    }
+
+    #[test]
+    fn unpriced_transfers_are_counted_at_zero_cost() {
+        let transactions = vec![
+            Transaction::received(1000, 1),
+            priced(Direction::Out, 1000, 300.0, 2),
+        ];
+        let report = report(CostBasisMethod::Fifo, &transactions);
+
+        assert_eq!(report.unpriced, 1);
+        assert_close(report.disposals[0].cost_basis, 0.0);
+        assert_close(report.realized_gain(), 300.0);
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::errors::WalletError;
+
+/// A fiat quote currency, identified by its ISO 4217 code.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Currency(String);
+
+impl Currency {
+    /// Parses a three-letter ISO 4217 code, case-insensitively.
+    pub fn parse(code: &str) -> Result<Currency, WalletError> {
+        let code = code.trim();
+        if code.len() != 3 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(WalletError::InvalidCurrency {
+                code: code.to_string(),
+            });
+        }
+        Ok(Currency(code.to_uppercase()))
+    }
+
+    /// Returns the uppercase ISO 4217 code, e.g. `EUR`.
+    pub fn code(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the symbol written before amounts, or `None` for currencies
+    /// usually written with their code, such as CHF.
+    pub fn symbol(&self) -> Option<&'static str> {
+        match self.code() {
+            "USD" => Some("$"),
+            "EUR" => Some("€"),
+            "GBP" => Some("£"),
+            "JPY" | "CNY" => Some("¥"),
+            "KRW" => Some("₩"),
+            "INR" => Some("₹"),
+            "RUB" => Some("₽"),
+            "TRY" => Some("₺"),
+            "ILS" => Some("₪"),
+            "UAH" => Some("₴"),
+            "BRL" => Some("R$"),
+            "CAD" => Some("CA$"),
+            "AUD" => Some("A$"),
+            "NZD" => Some("NZ$"),
+            "HKD" => Some("HK$"),
+            "SGD" => Some("S$"),
+            "MXN" => Some("MX$"),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of minor-unit decimals amounts are rounded to.
+    pub fn decimals(&self) -> usize {
+        match self.code() {
+            "JPY" | "KRW" | "ISK" | "CLP" | "VND" | "PYG" | "UGX" => 0,
+            "BHD" | "KWD" | "OMR" | "JOD" | "TND" | "LYD" | "IQD" => 3,
+            _ => 2,
+        }
+    }
+
+    /// Formats an amount with the currency's symbol and decimals, e.g. `€1234.50` or `CHF 980.00`.
+    pub fn format(&self, amount: f64) -> String {
+        let sign = if amount < 0.0 { "-" } else { "" };
+        let digits = format!("{:.*}", self.decimals(), amount.abs());
+        match self.symbol() {
+            Some(symbol) => format!("{}{}{}", sign, symbol, digits),
+            None => format!("{}{} {}", sign, self.code(), digits),
+        }
+    }
+
+    /// Rounds an amount to the currency's decimals without a symbol, for CSV exports.
+    pub fn format_plain(&self, amount: f64) -> String {
+        format!("{:.*}", self.decimals(), amount)
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Currency("USD".to_string())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = WalletError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        Currency::parse(code)
+    }
+}
+
+impl TryFrom<String> for Currency {
+    type Error = WalletError;
+
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        Currency::parse(&code)
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// An amount of fiat money in a given currency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiatAmount {
+    pub currency: Currency,
+    pub amount: f64,
+}
+
+impl fmt::Display for FiatAmount {
+    /// Formats the amount with its currency symbol and decimals.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.currency.format(self.amount))
+    }
+}
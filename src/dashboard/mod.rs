@@ -0,0 +1,230 @@
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use tokio::sync::mpsc;
+use tokio::time::{self, MissedTickBehavior};
+
+use crate::{
+    address::Address,
+    amount::Wei,
+    currency::FiatAmount,
+    errors::WalletError,
+    ethereum::chain::Chain,
+    structs::{Statistics, Transaction},
+    wallet_traits::WalletDashboard,
+};
+
+pub mod ui;
+
+/// Number of recent transactions listed by the dashboard when `--limit` is omitted,
+/// and in the interactive menu.
+pub const DEFAULT_DASHBOARD_LIMIT: usize = 100;
+
+/// Seconds between two refreshes when `--refresh` is omitted, and in the interactive menu.
+pub const DEFAULT_REFRESH_SECS: u64 = 60;
+
+/// How long the input thread waits for a key before checking whether the dashboard closed.
+const INPUT_POLL: Duration = Duration::from_millis(100);
+
+/// Number of rows skipped by Page Up and Page Down.
+const PAGE: usize = 10;
+
+/// Everything the dashboard shows, loaded in one go.
+#[derive(Debug)]
+pub struct DashboardSnapshot {
+    /// Wallet the data belongs to.
+    pub address: Address,
+    /// Chain the data was loaded from.
+    pub chain: Chain,
+    /// Native balance.
+    pub balance: Wei,
+    /// Balance in each configured currency.
+    pub fiat: Vec<FiatAmount>,
+    /// Most recent transactions, newest first, valued in the configured currencies.
+    pub transactions: Vec<Transaction>,
+    /// Statistics over the complete history, or `None` if the wallet has no transactions.
+    pub statistics: Option<Statistics>,
+}
+
+/// State of the dashboard between two frames.
+pub struct Dashboard {
+    /// Last successfully loaded data, if any.
+    pub snapshot: Option<DashboardSnapshot>,
+    /// Local time of the last successful refresh.
+    pub updated: Option<DateTime<Local>>,
+    /// Error of the last refresh, cleared by the next successful one.
+    pub error: Option<String>,
+    /// Whether a refresh is in progress.
+    pub loading: bool,
+    /// Index of the selected transaction.
+    pub selected: usize,
+    /// Whether the selected transaction is shown in detail.
+    pub detail: bool,
+    /// Time between two refreshes.
+    pub refresh: Duration,
+    quit: bool,
+}
+
+impl Dashboard {
+    /// Creates an empty dashboard waiting for its first snapshot.
+    pub fn new(refresh: Duration) -> Dashboard {
+        Dashboard {
+            snapshot: None,
+            updated: None,
+            error: None,
+            loading: true,
+            selected: 0,
+            detail: false,
+            refresh,
+            quit: false,
+        }
+    }
+
+    /// Returns the transactions of the current snapshot.
+    pub fn transactions(&self) -> &[Transaction] {
+        self.snapshot
+            .as_ref()
+            .map_or(&[], |snapshot| &snapshot.transactions)
+    }
+
+    /// Returns the selected transaction, if any.
+    pub fn selected_transaction(&self) -> Option<&Transaction> {
+        self.transactions().get(self.selected)
+    }
+
+    /// Records the outcome of a refresh, keeping the previous data on error.
+    ///
+    /// The selection follows the selected transaction when newer ones are
+    /// added on top of the list.
+    fn apply(&mut self, result: Result<DashboardSnapshot, WalletError>) {
+        self.loading = false;
+        match result {
+            Ok(snapshot) => {
+                let selected_hash = self.selected_transaction().map(|tx| tx.hash.clone());
+                self.selected = selected_hash
+                    .and_then(|hash| snapshot.transactions.iter().position(|tx| tx.hash == hash))
+                    .unwrap_or(0);
+                self.snapshot = Some(snapshot);
+                self.updated = Some(Local::now());
+                self.error = None;
+            }
+            Err(error) => self.error = Some(error.to_string()),
+        }
+    }
+
+    /// Moves the selection by `offset` rows, staying within the list.
+    fn select(&mut self, offset: isize) {
+        let last = self.transactions().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(offset).min(last);
+    }
+
+    /// Updates the state for a key press.
+    ///
+    /// # Returns
+    /// * `true` if the key asks for an immediate refresh.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Esc if self.detail => self.detail = false,
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Enter => self.detail = !self.detail && self.selected_transaction().is_some(),
+            KeyCode::Char('r') => return true,
+            KeyCode::Up | KeyCode::Char('k') => self.select(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.select(1),
+            KeyCode::PageUp => self.select(-(PAGE as isize)),
+            KeyCode::PageDown => self.select(PAGE as isize),
+            KeyCode::Home | KeyCode::Char('g') => self.selected = 0,
+            KeyCode::End | KeyCode::Char('G') => self.select(isize::MAX),
+            _ => {}
+        }
+        false
+    }
+}
+
+/// Runs the full-screen dashboard until the user quits.
+///
+/// Data is loaded through [`WalletDashboard::dashboard_snapshot`] every
+/// `refresh`, or on demand with `r`. Loading runs alongside input handling,
+/// so the dashboard stays responsive while a refresh is in progress, and a
+/// failed refresh keeps the previous data on screen.
+///
+/// # Arguments
+/// * `account` - Wallet to show.
+/// * `limit` - Number of recent transactions to list.
+/// * `refresh` - Time between two automatic refreshes.
+///
+/// # Errors
+/// Returns an error if the terminal cannot be drawn to.
+pub async fn run_dashboard<T: WalletDashboard + ?Sized>(
+    account: &T,
+    limit: usize,
+    refresh: Duration,
+) -> Result<(), WalletError> {
+    let mut terminal = ratatui::init();
+    let mut dashboard = Dashboard::new(refresh);
+
+    // Key presses are read on their own thread, which stops once the
+    // receiving end is dropped.
+    let (key_sender, mut keys) = mpsc::unbounded_channel();
+    let input = thread::spawn(move || {
+        while !key_sender.is_closed() {
+            match event::poll(INPUT_POLL) {
+                Ok(true) => {
+                    if let Ok(Event::Key(key)) = event::read()
+                        && key.kind == KeyEventKind::Press
+                        && key_sender.send(key).is_err()
+                    {
+                        break;
+                    }
+                }
+                Ok(false) => {}
+                Err(_) => break,
+            }
+        }
+    });
+
+    let mut ticker = time::interval(refresh);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut pending = None;
+
+    let result = loop {
+        if let Err(error) = terminal.draw(|frame| ui::draw(frame, &dashboard)) {
+            break Err(WalletError::from(error));
+        }
+
+        tokio::select! {
+            key = keys.recv() => match key {
+                Some(key) => {
+                    if dashboard.handle_key(key) && pending.is_none() {
+                        dashboard.loading = true;
+                        pending = Some(account.dashboard_snapshot(limit));
+                    }
+                }
+                None => break Ok(()),
+            },
+            result = async { pending.as_mut().expect("refresh in progress").await },
+                if pending.is_some() =>
+            {
+                pending = None;
+                dashboard.apply(result);
+            }
+            _ = ticker.tick(), if pending.is_none() => {
+                dashboard.loading = true;
+                pending = Some(account.dashboard_snapshot(limit));
+            }
+        }
+
+        if dashboard.quit {
+            break Ok(());
+        }
+    };
+
+    drop(keys);
+    let _ = input.join();
+    ratatui::restore();
+    result
+}
+
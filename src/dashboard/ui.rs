@@ -0,0 +1,212 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
+};
+
+use super::Dashboard;
+use crate::enums::Direction;
+
+/// Draws one frame of the dashboard.
+///
+/// The screen is split into a status bar, the balance and fiat panes, the
+/// transaction list next to the statistics, and a key help line. The
+/// selected transaction is drawn in a popup when detail is open.
+pub fn draw(frame: &mut Frame, dashboard: &Dashboard) {
+    let [status, balances, body, help] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(4),
+        Constraint::Min(5),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [balance, fiat] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .areas(balances);
+    let [transactions, statistics] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(body);
+
+    draw_status(frame, dashboard, status);
+    draw_balance(frame, dashboard, balance);
+    draw_fiat(frame, dashboard, fiat);
+    draw_transactions(frame, dashboard, transactions);
+    draw_statistics(frame, dashboard, statistics);
+    frame.render_widget(
+        Paragraph::new(format!(
+            "↑/↓ select  PgUp/PgDn page  Enter detail  r refresh (auto every {}s)  q quit",
+            dashboard.refresh.as_secs()
+        ))
+        .style(Style::new().fg(Color::DarkGray)),
+        help,
+    );
+
+    if dashboard.detail {
+        draw_detail(frame, dashboard);
+    }
+}
+
+/// Draws the wallet and the state of the last refresh.
+fn draw_status(frame: &mut Frame, dashboard: &Dashboard, area: Rect) {
+    let title = match &dashboard.snapshot {
+        Some(snapshot) => format!("{} on {}", snapshot.address, snapshot.chain),
+        None => "Wallet dashboard".to_string(),
+    };
+    let state = match (&dashboard.error, dashboard.loading, dashboard.updated) {
+        (_, true, _) => Span::styled("refreshing…", Style::new().fg(Color::Yellow)),
+        (Some(error), false, _) => Span::styled(
+            format!("refresh failed: {}", error),
+            Style::new().fg(Color::Red),
+        ),
+        (None, false, Some(updated)) => {
+            Span::raw(format!("updated {}", updated.format("%H:%M:%S")))
+        }
+        (None, false, None) => Span::raw(""),
+    };
+
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!("{}  ", title),
+                Style::new().add_modifier(Modifier::BOLD),
+            ),
+            state,
+        ])),
+        area,
+    );
+}
+
+/// Draws the native balance.
+fn draw_balance(frame: &mut Frame, dashboard: &Dashboard, area: Rect) {
+    let text = match &dashboard.snapshot {
+        Some(snapshot) => format!(
+            "{} {}",
+            snapshot.balance.to_ether_string(),
+            snapshot.chain.native_symbol()
+        ),
+        None => "loading…".to_string(),
+    };
+
+    frame.render_widget(
+        Paragraph::new(text).block(Block::new().borders(Borders::ALL).title(" Balance ")),
+        area,
+    );
+}
+
+/// Draws the balance in each configured currency.
+fn draw_fiat(frame: &mut Frame, dashboard: &Dashboard, area: Rect) {
+    let text = match &dashboard.snapshot {
+        Some(snapshot) => snapshot
+            .fiat
+            .iter()
+            .map(|amount| amount.to_string())
+            .collect::<Vec<_>>()
+            .join("  "),
+        None => "loading…".to_string(),
+    };
+
+    frame.render_widget(
+        Paragraph::new(text)
+            .wrap(Wrap { trim: true })
+            .block(Block::new().borders(Borders::ALL).title(" Fiat value ")),
+        area,
+    );
+}
+
+/// Draws the scrollable list of recent transactions, newest first.
+fn draw_transactions(frame: &mut Frame, dashboard: &Dashboard, area: Rect) {
+    let rows = dashboard.transactions().iter().map(|tx| {
+        let (label, color) = match tx.direction {
+            Direction::In => ("in", Color::Green),
+            Direction::Out => ("out", Color::Red),
+            Direction::ContractCreation => ("create", Color::Red),
+            Direction::SelfTransfer => ("self", Color::Gray),
+            Direction::Unrelated => ("other", Color::Gray),
+        };
+        Row::new(vec![
+            Cell::from(tx.date.clone()),
+            Cell::from(label).style(Style::new().fg(color)),
+            Cell::from(format!(
+                "{} {}",
+                tx.quantity.to_ether_string(),
+                tx.chain.native_symbol()
+            )),
+            Cell::from(if tx.internal { "internal" } else { "" }),
+            Cell::from(tx.hash.get(..12).unwrap_or(&tx.hash).to_string()),
+        ])
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(19),
+            Constraint::Length(6),
+            Constraint::Min(14),
+            Constraint::Length(8),
+            Constraint::Length(12),
+        ],
+    )
+    .header(
+        Row::new(vec!["Date", "Dir", "Value", "", "Hash"])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+    .block(Block::new().borders(Borders::ALL).title(format!(
+        " Transactions ({}) ",
+        dashboard.transactions().len()
+    )));
+
+    let mut state = TableState::default().with_selected(Some(dashboard.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+/// Draws the statistics over the complete history.
+fn draw_statistics(frame: &mut Frame, dashboard: &Dashboard, area: Rect) {
+    let text = match &dashboard.snapshot {
+        Some(snapshot) => match &snapshot.statistics {
+            Some(statistics) => statistics.to_string(),
+            None => "No transactions for this address".to_string(),
+        },
+        None => "loading…".to_string(),
+    };
+
+    frame.render_widget(
+        Paragraph::new(text)
+            .wrap(Wrap { trim: true })
+            .block(Block::new().borders(Borders::ALL).title(" Statistics ")),
+        area,
+    );
+}
+
+/// Draws the selected transaction in a popup over the middle of the screen.
+fn draw_detail(frame: &mut Frame, dashboard: &Dashboard) {
+    let Some(tx) = dashboard.selected_transaction() else {
+        return;
+    };
+
+    let [_, area, _] = Layout::vertical([
+        Constraint::Percentage(20),
+        Constraint::Percentage(60),
+        Constraint::Percentage(20),
+    ])
+    .areas(frame.area());
+    let [_, area, _] = Layout::horizontal([
+        Constraint::Percentage(10),
+        Constraint::Percentage(80),
+        Constraint::Percentage(10),
+    ])
+    .areas(area);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(format!("{}\nHash: {}", tx, tx.hash))
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::new()
+                    .borders(Borders::ALL)
+                    .title(" Transaction (Esc to close) "),
+            ),
+        area,
+    );
+}
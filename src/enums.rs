@@ -1,3 +1,7 @@
+use std::fmt;
+
+use clap::ValueEnum;
+
 /// Represents the different user actions available in the wallet tracker CLI.
 #[derive(Debug)]
 pub enum Action {
@@ -5,14 +9,140 @@ pub enum Action {
     Balance,
     /// Display the wallet's balance in fiat currency (e.g., USD).
     Fiat,
+    /// Display the wallet's ERC-20 token balances.
+    Tokens,
+    /// Display the NFTs held by the wallet and how it acquired them.
+    Nfts,
     /// Show recent transactions for the wallet.
     Transactions,
     /// Display calculated statistics for the wallet.
     Stats,
     /// Export wallet data to a file.
     Export,
-    /// Show the average gas used in recent transactions.
+    /// Show the total and average fees paid in recent transactions.
     Gas,
+    /// Display realized and unrealized gains of the native currency.
+    CostBasis,
+    /// Show the addresses the wallet transacts with the most.
+    Counterparties,
+    /// Show the wallet's balance over time.
+    BalanceHistory,
+    /// Draw a chart of the wallet's history.
+    Chart,
+    /// Open the full-screen dashboard.
+    Dashboard,
     /// Exit the application.
     Exit,
 }
+
+/// Direction of a transfer relative to the tracked wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The wallet received the value.
+    In,
+    /// The wallet sent the value.
+    Out,
+    /// The wallet sent the value to itself.
+    SelfTransfer,
+    /// The wallet deployed a contract, sending it any value attached.
+    ContractCreation,
+    /// The wallet is neither the sender nor the recipient, so the transfer
+    /// does not change its balance.
+    Unrelated,
+}
+
+impl Direction {
+    /// Determines the direction of a transfer between `from` and `to` for `wallet`.
+    ///
+    /// Addresses are compared case-insensitively. A transfer without a
+    /// recipient deploys a contract.
+    pub fn of(wallet: &str, from: &str, to: &str) -> Direction {
+        let sent = from.eq_ignore_ascii_case(wallet);
+        let received = to.eq_ignore_ascii_case(wallet);
+
+        match (sent, received) {
+            (true, _) if to.is_empty() => Direction::ContractCreation,
+            (true, true) => Direction::SelfTransfer,
+            (true, false) => Direction::Out,
+            (false, true) => Direction::In,
+            (false, false) => Direction::Unrelated,
+        }
+    }
+
+    /// Returns whether value enters the wallet in this direction.
+    pub fn is_inflow(self) -> bool {
+        self == Direction::In
+    }
+
+    /// Returns whether value leaves the wallet in this direction.
+    pub fn is_outflow(self) -> bool {
+        matches!(self, Direction::Out | Direction::ContractCreation)
+    }
+
+    /// Returns whether the wallet sent the transaction, and so paid its fee.
+    pub fn is_sent(self) -> bool {
+        matches!(
+            self,
+            Direction::Out | Direction::SelfTransfer | Direction::ContractCreation
+        )
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Direction::In => "in",
+            Direction::Out => "out",
+            Direction::SelfTransfer => "self",
+            Direction::ContractCreation => "contract-creation",
+            Direction::Unrelated => "unrelated",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Token standard of an NFT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NftStandard {
+    /// Non-fungible token, one owner per token id.
+    Erc721,
+    /// Multi-token, any amount per token id.
+    Erc1155,
+}
+
+impl NftStandard {
+    /// Parses the label produced by `Display`.
+    pub fn from_label(label: &str) -> Option<NftStandard> {
+        match label {
+            "ERC-721" => Some(NftStandard::Erc721),
+            "ERC-1155" => Some(NftStandard::Erc1155),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for NftStandard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            NftStandard::Erc721 => "ERC-721",
+            NftStandard::Erc1155 => "ERC-1155",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Order in which counterparties are listed, largest first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum CounterpartySort {
+    /// Number of transactions.
+    #[default]
+    Count,
+    /// Value received from the counterparty.
+    VolumeIn,
+    /// Value sent to the counterparty.
+    VolumeOut,
+    /// Value received and sent.
+    Volume,
+    /// Most recent interaction.
+    Recent,
+}
@@ -15,12 +15,18 @@ pub enum WalletError {
     #[error("Integer parse error: {0}")]
     FloatParse(#[from] std::num::ParseFloatError),
 
-    #[error("Invalid wallet address: {address}")]
-    InvalidAddress { address: String },
+    #[error("Invalid amount: {value}")]
+    AmountParse { value: String },
+
+    #[error("Invalid address {address}: {reason}")]
+    InvalidAddress { address: String, reason: String },
 
     #[error("Missing field: {field}")]
     Missing { field: String },
 
+    #[error("Etherscan error: {message}")]
+    Etherscan { message: String },
+
     #[error("No transactions for this address")]
     NoTransactions,
 
@@ -29,14 +35,34 @@ pub enum WalletError {
 
     #[error("CSV error: {0}")]
     Csv(#[from] csv::Error),
+
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("JSON-RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+
+    #[error("Config file error: {0}")]
+    Config(#[from] toml::de::Error),
+
+    #[error("Invalid currency code: {code}")]
+    InvalidCurrency { code: String },
+
+    #[error("No price available for {asset} in {currency}")]
+    PriceUnavailable { asset: String, currency: String },
+
+    #[error("{provider} error: {message}")]
+    PriceApi { provider: String, message: String },
 }
 
 impl WalletError {
     pub fn display_error(&self) {
         match self {
-            WalletError::InvalidAddress { address } => {
-                eprintln!("Invalid wallet address: {}", address);
-                eprintln!("Addresses should start with 0x and be 42 characters long");
+            WalletError::InvalidAddress { address, reason } => {
+                eprintln!("Invalid address {}: {}", address, reason);
+                eprintln!(
+                    "Addresses are 0x followed by 40 hex digits, optionally EIP-55 checksummed"
+                );
             }
             WalletError::Network(req_err) => {
                 eprintln!("Network error: {}", req_err);
@@ -58,6 +84,14 @@ impl WalletError {
                 eprintln!("Float parse error: {}", err);
                 eprintln!("The data might contain invalid decimal values");
             }
+            WalletError::AmountParse { value } => {
+                eprintln!("Invalid amount: {}", value);
+                eprintln!("Amounts must be unsigned integers that fit in 256 bits");
+            }
+            WalletError::Etherscan { message } => {
+                eprintln!("Etherscan error: {}", message);
+                eprintln!("Check your ETHERSCAN_KEY and the API rate limits of your plan");
+            }
             WalletError::NoTransactions => {
                 eprintln!("No transactions found for this address");
                 eprintln!("Try again later or verify the address has activity");
@@ -70,6 +104,32 @@ impl WalletError {
                 eprintln!("CSV error: {}", err);
                 eprintln!("Ensure the CSV file is properly formatted");
             }
+            WalletError::Database(err) => {
+                eprintln!("Database error: {}", err);
+                eprintln!("Delete the local cache file to force a full re-sync");
+            }
+            WalletError::Rpc { code, message } => {
+                eprintln!("JSON-RPC error {}: {}", code, message);
+                eprintln!(
+                    "Check that the configured RPC provider is reachable and supports this call"
+                );
+            }
+            WalletError::Config(err) => {
+                eprintln!("Config file error: {}", err);
+                eprintln!("Check the syntax and keys of your wallet-tracker.toml");
+            }
+            WalletError::InvalidCurrency { code } => {
+                eprintln!("Invalid currency code: {}", code);
+                eprintln!("Currencies are three-letter ISO 4217 codes such as USD, EUR or CHF");
+            }
+            WalletError::PriceUnavailable { asset, currency } => {
+                eprintln!("No price available for {} in {}", asset, currency);
+                eprintln!("Every configured price source failed; add a price file as a fallback");
+            }
+            WalletError::PriceApi { provider, message } => {
+                eprintln!("{} error: {}", provider, message);
+                eprintln!("The price source may be down or rate limiting requests");
+            }
         }
     }
 }
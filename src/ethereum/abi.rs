@@ -0,0 +1,334 @@
+use primitive_types::U256;
+use serde_json::json;
+use tiny_keccak::{Hasher, Keccak};
+
+use super::provider::Provider;
+use crate::errors::WalletError;
+
+/// Size of one ABI word in hex characters.
+const WORD_HEX_LEN: usize = 64;
+
+/// Executes a read-only contract call against the latest block.
+///
+/// # Arguments
+/// * `provider` - JSON-RPC provider to query.
+/// * `to` - Contract address.
+/// * `data` - `0x`-prefixed calldata: selector followed by encoded arguments.
+///
+/// # Returns
+/// * The raw return data as a hex string without the `0x` prefix.
+pub async fn eth_call(provider: &Provider, to: &str, data: &str) -> Result<String, WalletError> {
+    let response = provider
+        .request("eth_call", json!([{ "to": to, "data": data }, "latest"]))
+        .await?;
+
+    let hex_data = response.as_str().ok_or(WalletError::Missing {
+        field: "result".to_string(),
+    })?;
+
+    Ok(hex_data.trim_start_matches("0x").to_string())
+}
+
+/// Encodes an address as a left-padded ABI word.
+pub fn encode_address(address: &str) -> String {
+    format!(
+        "{:0>width$}",
+        address.trim_start_matches("0x").to_lowercase(),
+        width = WORD_HEX_LEN
+    )
+}
+
+/// Decodes the first word of hex return data as an address, `0x`-prefixed and lowercase.
+pub fn decode_address(data: &str) -> Result<String, WalletError> {
+    let word = word(data, 0)?;
+    Ok(format!("0x{}", word[WORD_HEX_LEN - 40..].to_lowercase()))
+}
+
+/// Computes the Keccak-256 hash used throughout Ethereum.
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    let mut keccak = Keccak::v256();
+    keccak.update(data);
+    keccak.finalize(&mut hash);
+    hash
+}
+
+/// Returns the `index`-th ABI word of hex return data.
+fn word(data: &str, index: usize) -> Result<&str, WalletError> {
+    let start = index * WORD_HEX_LEN;
+    data.get(start..start + WORD_HEX_LEN)
+        .ok_or(WalletError::Missing {
+            field: format!("return word {}", index),
+        })
+}
+
+/// Decodes the first word of hex return data as an unsigned integer.
+pub fn decode_uint(data: &str) -> Result<U256, WalletError> {
+    decode_uint_at(data, 0)
+}
+
+/// Decodes the `index`-th word of hex data as an unsigned integer.
+pub fn decode_uint_at(data: &str, index: usize) -> Result<U256, WalletError> {
+    U256::from_str_radix(word(data, index)?, 16).map_err(|_| WalletError::AmountParse {
+        value: data.to_string(),
+    })
+}
+
+/// Decodes the first word of hex return data as a `uint8`, e.g. the result of `decimals()`.
+///
+/// # Errors
+/// Returns [`WalletError::AmountParse`] if the value does not fit in a `uint8`.
+pub fn decode_u8(data: &str) -> Result<u8, WalletError> {
+    let value = decode_uint(data)?;
+    if value > U256::from(u8::MAX) {
+        return Err(WalletError::AmountParse {
+            value: value.to_string(),
+        });
+    }
+    Ok(value.low_u32() as u8)
+}
+
+/// Decodes a dynamic `uint256[]` whose offset is stored in the `index`-th word.
+///
+/// # Errors
+/// Returns [`WalletError::Missing`] if the offset or length points past the data.
+pub fn decode_uint_array(data: &str, index: usize) -> Result<Vec<U256>, WalletError> {
+    let missing = || WalletError::Missing {
+        field: "array data".to_string(),
+    };
+    let offset = hex_len(decode_uint_at(data, index)?).ok_or_else(missing)?;
+    let array = data.get(offset..).unwrap_or_default();
+    // The elements follow the length word, so the data bounds how many there can be.
+    let length = u64::try_from(decode_uint(array)?)
+        .ok()
+        .and_then(|length| usize::try_from(length).ok())
+        .filter(|&length| length < array.len() / WORD_HEX_LEN)
+        .ok_or_else(missing)?;
+
+    (1..=length)
+        .map(|element| decode_uint_at(array, element))
+        .collect()
+}
+
+/// Decodes hex return data as a string.
+///
+/// Handles both the standard dynamic `string` encoding and the `bytes32`
+/// encoding still returned by some older tokens, such as MKR.
+pub fn decode_string(data: &str) -> Result<String, WalletError> {
+    let bytes = if data.len() == WORD_HEX_LEN {
+        hex_to_bytes(data)?
+            .into_iter()
+            .take_while(|byte| *byte != 0)
+            .collect()
+    } else {
+        let missing = || WalletError::Missing {
+            field: "string data".to_string(),
+        };
+        let offset = hex_len(decode_uint(data)?).ok_or_else(missing)?;
+        let length =
+            hex_len(decode_uint(data.get(offset..).unwrap_or_default())?).ok_or_else(missing)?;
+        let start = offset.checked_add(WORD_HEX_LEN).ok_or_else(missing)?;
+        let end = start.checked_add(length).ok_or_else(missing)?;
+        let content = data.get(start..end).ok_or_else(missing)?;
+        hex_to_bytes(content)?
+    };
+
+    Ok(String::from_utf8_lossy(&bytes).trim().to_string())
+}
+
+/// Converts a byte count or offset read from an ABI word into a number of hex digits.
+///
+/// # Returns
+/// * `None` if the value does not fit in memory.
+fn hex_len(bytes: U256) -> Option<usize> {
+    u64::try_from(bytes)
+        .ok()
+        .and_then(|bytes| usize::try_from(bytes).ok())
+        .and_then(|bytes| bytes.checked_mul(2))
+}
+
+/// Converts a hex string without prefix into bytes.
+pub fn hex_to_bytes(hex_data: &str) -> Result<Vec<u8>, WalletError> {
+    (0..hex_data.len() / 2)
+        .map(|i| {
+            let pair = hex_data.get(i * 2..i * 2 + 2).ok_or(WalletError::Missing {
+                field: format!("hex byte {}", i),
+            })?;
+            Ok(u8::from_str_radix(pair, 16)?)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes a value as one ABI word.
+    fn encode_word(value: U256) -> String {
+        format!("{:0>64}", format!("{:x}", value))
+    }
+
+    /// Encodes bytes right-padded to whole words.
+    fn encode_bytes(bytes: &[u8]) -> String {
+        let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let words = hex.len().div_ceil(WORD_HEX_LEN).max(1);
+        format!("{:0<width$}", hex, width = words * WORD_HEX_LEN)
+    }
+
+    /// Encodes a dynamic `string` return value.
+    fn encode_string(value: &str) -> String {
+        encode_word(U256::from(32))
+            + &encode_word(U256::from(value.len()))
+            + &encode_bytes(value.as_bytes())
+    }
+
+    fn is_missing<T: std::fmt::Debug>(result: Result<T, WalletError>) -> bool {
+        matches!(result, Err(WalletError::Missing { .. }))
+    }
+
+    #[test]
+    fn keccak256_matches_known_digest() {
+        let hex: String = keccak256(b"")
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        assert_eq!(
+            hex,
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
+
+    #[test]
+    fn addresses_round_trip_through_a_word() {
+        let address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let word = encode_address(address);
+        assert_eq!(word.len(), WORD_HEX_LEN);
+        assert!(word.starts_with(&"0".repeat(24)));
+        assert_eq!(decode_address(&word).unwrap(), address.to_lowercase());
+    }
+
+    #[test]
+    fn decodes_uint_words() {
+        let data = encode_word(U256::from(7)) + &encode_word(U256::MAX);
+        assert_eq!(decode_uint(&data).unwrap(), U256::from(7));
+        assert_eq!(decode_uint_at(&data, 1).unwrap(), U256::MAX);
+        assert!(is_missing(decode_uint_at(&data, 2)));
+        assert!(is_missing(decode_uint("")));
+        assert!(matches!(
+            decode_uint(&"z".repeat(WORD_HEX_LEN)),
+            Err(WalletError::AmountParse { .. })
+        ));
+    }
+
+    #[test]
+    fn decodes_uint8_words() {
+        assert_eq!(decode_u8(&encode_word(U256::from(18))).unwrap(), 18);
+        assert_eq!(decode_u8(&encode_word(U256::from(255))).unwrap(), 255);
+        for value in [U256::from(256), U256::from(u32::MAX) + 18, U256::MAX] {
+            assert!(matches!(
+                decode_u8(&encode_word(value)),
+                Err(WalletError::AmountParse { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn decodes_dynamic_strings() {
+        assert_eq!(decode_string(&encode_string("USDC")).unwrap(), "USDC");
+        assert_eq!(decode_string(&encode_string("")).unwrap(), "");
+        let long = "A token name longer than a single ABI word";
+        assert_eq!(decode_string(&encode_string(long)).unwrap(), long);
+    }
+
+    #[test]
+    fn decodes_bytes32_strings() {
+        assert_eq!(decode_string(&encode_bytes(b"MKR")).unwrap(), "MKR");
+    }
+
+    #[test]
+    fn rejects_truncated_strings() {
+        let data = encode_string("USDC");
+        assert!(is_missing(decode_string(&data[..2 * WORD_HEX_LEN + 4])));
+        assert!(is_missing(decode_string(&data[..WORD_HEX_LEN + 10])));
+    }
+
+    #[test]
+    fn rejects_overflowing_string_offsets_and_lengths() {
+        let huge_offset = encode_word(U256::MAX) + &encode_word(U256::from(4));
+        assert!(is_missing(decode_string(&huge_offset)));
+
+        for length in [U256::MAX, U256::from(u64::MAX), U256::from(1u64 << 62)] {
+            let data = encode_word(U256::from(32)) + &encode_word(length) + &encode_bytes(b"USDC");
+            assert!(is_missing(decode_string(&data)), "length {}", length);
+        }
+
+        let offset_near_end = encode_word(U256::from(usize::MAX / 2)) + &encode_word(U256::zero());
+        assert!(decode_string(&offset_near_end).is_err());
+    }
+
+    /// Encodes a dynamic `uint256[]` without its offset.
+    fn encode_array(values: &[u64]) -> String {
+        values
+            .iter()
+            .fold(encode_word(U256::from(values.len())), |data, value| {
+                data + &encode_word(U256::from(*value))
+            })
+    }
+
+    /// Returns the low 64 bits of each decoded value.
+    fn abi_values(values: Vec<U256>) -> Vec<u64> {
+        values.iter().map(U256::low_u64).collect()
+    }
+
+    #[test]
+    fn decodes_uint_arrays() {
+        // `TransferBatch` data: offsets of the ids and of the amounts, then both arrays.
+        let ids = encode_array(&[1, 2, 3]);
+        let amounts = encode_array(&[10, 20, 30]);
+        let data = encode_word(U256::from(64))
+            + &encode_word(U256::from(64 + ids.len() / 2))
+            + &ids
+            + &amounts;
+
+        let decode = |index| abi_values(decode_uint_array(&data, index).unwrap());
+        assert_eq!(decode(0), [1, 2, 3]);
+        assert_eq!(decode(1), [10, 20, 30]);
+
+        let empty = encode_word(U256::from(32)) + &encode_array(&[]);
+        assert!(decode_uint_array(&empty, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_arrays_longer_than_their_data() {
+        let truncated = encode_word(U256::from(32)) + &encode_array(&[1, 2, 3])[..3 * WORD_HEX_LEN];
+        assert!(is_missing(decode_uint_array(&truncated, 0)));
+
+        for length in [U256::MAX, U256::from(u64::MAX), U256::from(1u64 << 40)] {
+            let data =
+                encode_word(U256::from(32)) + &encode_word(length) + &encode_word(U256::one());
+            assert!(is_missing(decode_uint_array(&data, 0)), "length {}", length);
+        }
+    }
+
+    #[test]
+    fn rejects_overflowing_array_offsets() {
+        for offset in [U256::MAX, U256::from(u64::MAX)] {
+            let data = encode_word(offset) + &encode_array(&[1]);
+            assert!(is_missing(decode_uint_array(&data, 0)), "offset {}", offset);
+        }
+
+        // An offset past the end leaves no length word to read.
+        let data = encode_word(U256::from(1024)) + &encode_array(&[1]);
+        assert!(is_missing(decode_uint_array(&data, 0)));
+    }
+
+    #[test]
+    fn rejects_non_hex_string_data() {
+        // The two-byte character straddles the first hex pair.
+        let data =
+            encode_word(U256::from(32)) + &encode_word(U256::from(2)) + "0é0" + &"0".repeat(60);
+        assert!(decode_string(&data).is_err());
+        assert!(hex_to_bytes("zz").is_err());
+        assert_eq!(hex_to_bytes("00ff").unwrap(), [0, 255]);
+    }
+}
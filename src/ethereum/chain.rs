@@ -0,0 +1,103 @@
+use std::fmt;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// An EVM chain the tracker can query.
+///
+/// Every chain is served by the same JSON-RPC and Etherscan v2 code paths;
+/// only the chain id, native currency and endpoints differ.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Chain {
+    /// Ethereum mainnet.
+    #[default]
+    Ethereum,
+    /// Arbitrum One.
+    Arbitrum,
+    /// OP Mainnet.
+    Optimism,
+    /// Base mainnet.
+    Base,
+    /// Polygon PoS.
+    Polygon,
+    /// BNB Smart Chain.
+    Bsc,
+}
+
+impl Chain {
+    /// Returns the EIP-155 chain id, also used as the Etherscan v2 `chainid`.
+    pub fn chain_id(self) -> u64 {
+        match self {
+            Chain::Ethereum => 1,
+            Chain::Arbitrum => 42161,
+            Chain::Optimism => 10,
+            Chain::Base => 8453,
+            Chain::Polygon => 137,
+            Chain::Bsc => 56,
+        }
+    }
+
+    /// Returns the lowercase name used on the CLI and in the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Chain::Ethereum => "ethereum",
+            Chain::Arbitrum => "arbitrum",
+            Chain::Optimism => "optimism",
+            Chain::Base => "base",
+            Chain::Polygon => "polygon",
+            Chain::Bsc => "bsc",
+        }
+    }
+
+    /// Returns the ticker of the chain's native currency.
+    pub fn native_symbol(self) -> &'static str {
+        match self {
+            Chain::Ethereum | Chain::Arbitrum | Chain::Optimism | Chain::Base => "ETH",
+            Chain::Polygon => "POL",
+            Chain::Bsc => "BNB",
+        }
+    }
+
+    /// Returns the public JSON-RPC endpoint used when none is configured.
+    pub fn default_rpc_url(self) -> &'static str {
+        match self {
+            Chain::Ethereum => "https://ethereum-rpc.publicnode.com",
+            Chain::Arbitrum => "https://arb1.arbitrum.io/rpc",
+            Chain::Optimism => "https://mainnet.optimism.io",
+            Chain::Base => "https://mainnet.base.org",
+            Chain::Polygon => "https://polygon-rpc.com",
+            Chain::Bsc => "https://bsc-dataseed.bnb.org",
+        }
+    }
+
+    /// Returns the environment variable overriding this chain's JSON-RPC endpoint.
+    pub fn rpc_url_env(self) -> &'static str {
+        match self {
+            Chain::Ethereum => "ETH_RPC_URL",
+            Chain::Arbitrum => "ARBITRUM_RPC_URL",
+            Chain::Optimism => "OPTIMISM_RPC_URL",
+            Chain::Base => "BASE_RPC_URL",
+            Chain::Polygon => "POLYGON_RPC_URL",
+            Chain::Bsc => "BSC_RPC_URL",
+        }
+    }
+
+    /// Returns the base URL of the chain's block explorer website.
+    pub fn explorer_url(self) -> &'static str {
+        match self {
+            Chain::Ethereum => "https://etherscan.io",
+            Chain::Arbitrum => "https://arbiscan.io",
+            Chain::Optimism => "https://optimistic.etherscan.io",
+            Chain::Base => "https://basescan.org",
+            Chain::Polygon => "https://polygonscan.com",
+            Chain::Bsc => "https://bscscan.com",
+        }
+    }
+}
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
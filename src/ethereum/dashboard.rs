@@ -0,0 +1,68 @@
+use async_trait::async_trait;
+
+use super::erc20::get_token_transfers;
+use super::ether_account::EtherAccount;
+use super::utils::{self, get_all_transactions, get_balance, get_fiat_balance};
+use crate::dashboard::DashboardSnapshot;
+use crate::errors::WalletError;
+use crate::wallet_traits;
+
+#[async_trait]
+impl wallet_traits::WalletDashboard for EtherAccount {
+    /// Loads the balances, recent transactions and statistics shown by the dashboard.
+    ///
+    /// The complete history is synced once and used for the statistics; only
+    /// the listed transactions are valued in the configured currencies.
+    ///
+    /// # Arguments
+    /// * `limit` - Number of recent transactions to list.
+    ///
+    /// # Errors
+    /// Returns an error if the balance, the prices or the history cannot be fetched.
+    async fn dashboard_snapshot(&self, limit: usize) -> Result<DashboardSnapshot, WalletError> {
+        let balance = get_balance(&self.provider, &self.wallet).await?;
+        let fiat = get_fiat_balance(
+            &self.provider,
+            &self.prices,
+            self.chain,
+            &self.wallet,
+            &self.currencies,
+        )
+        .await?;
+
+        let mut history =
+            get_all_transactions(&self.provider, &self.store, self.chain, &self.wallet).await?;
+        let token_transfers =
+            get_token_transfers(&self.store, self.chain, &self.wallet, None).await?;
+        let statistics = match utils::statistics_from_transactions(
+            self.chain,
+            &self.wallet,
+            &history,
+            &token_transfers,
+        ) {
+            Ok(statistics) => Some(statistics),
+            Err(WalletError::NoTransactions) => None,
+            Err(error) => return Err(error),
+        };
+
+        let mut transactions = history.split_off(history.len().saturating_sub(limit));
+        utils::value_transactions(
+            &self.store,
+            &self.prices,
+            self.chain,
+            &mut transactions,
+            &self.currencies,
+        )
+        .await?;
+        transactions.reverse();
+
+        Ok(DashboardSnapshot {
+            address: self.wallet.clone(),
+            chain: self.chain,
+            balance,
+            fiat,
+            transactions,
+            statistics,
+        })
+    }
+}
@@ -1,15 +1,21 @@
 use async_trait::async_trait;
+use chrono::Utc;
 
+use super::erc20::get_token_transfers;
 use super::ether_account::EtherAccount;
-use super::utils::{self, get_average_gas, get_transactions};
+use super::nft::{get_nft_transfers, nft_holdings};
+use super::utils::{self, get_fee_summary, get_transactions};
+use crate::balance_history::{self, SamplingInterval};
+use crate::charts::{self, ChartKind};
+use crate::enums::{CounterpartySort, Direction};
 use crate::errors::WalletError;
-use crate::ethereum::utils::{get_balance, get_fiat_balance};
+use crate::ethereum::utils::{get_balance, get_fiat_balance, get_token_balances};
 use crate::structs::Transaction;
-use crate::{input, wallet_traits};
+use crate::wallet_traits;
 
 #[async_trait]
 impl wallet_traits::WalletDisplay for EtherAccount {
-    /// Displays the wallet's ETH balance in the console.
+    /// Displays the wallet's native balance in the console.
     ///
     /// # Arguments
     /// * `wallet` - Wallet address as a string slice.
@@ -17,9 +23,14 @@ impl wallet_traits::WalletDisplay for EtherAccount {
     /// # Errors
     /// Returns an error if balance retrieval fails.
     async fn display_balance(&self) -> Result<(), WalletError> {
-        match get_balance(&self.wallet).await {
+        match get_balance(&self.provider, &self.wallet).await {
             Ok(value) => {
-                println!("The balance of the wallet in ether is: {}", value);
+                println!(
+                    "The balance of the wallet on {} is: {} {}",
+                    self.chain,
+                    value.to_ether_string(),
+                    self.chain.native_symbol()
+                );
                 Ok(())
             }
             Err(error) => {
@@ -29,48 +40,150 @@ impl wallet_traits::WalletDisplay for EtherAccount {
         }
     }
 
-    /// Displays the wallet's fiat (USD) balance in the console.
+    /// Displays the wallet's fiat balance in each configured quote currency.
     ///
-    /// Uses Coinbase exchange rates for conversion.
+    /// Uses the configured price sources for conversion.
     ///
     /// # Arguments
     /// * `wallet` - Wallet address as a string slice.
     async fn display_fiat(&self) -> Result<(), WalletError> {
-        let usd_balanace = get_fiat_balance(&self.wallet).await?;
-        println!("Balance in USD is: {}$\n", usd_balanace);
+        let fiat_balances = get_fiat_balance(
+            &self.provider,
+            &self.prices,
+            self.chain,
+            &self.wallet,
+            &self.currencies,
+        )
+        .await?;
+        for balance in &fiat_balances {
+            println!("Balance in {} is: {}", balance.currency, balance);
+        }
+        println!();
 
         Ok(())
     }
 
-    /// Displays a list of the wallet's transactions in detailed format.
+    /// Displays the wallet's ERC-20 token balances below its native balance.
     ///
-    /// The user is prompted for how many transactions to display.
+    /// Tokens come from the configured list and from the wallet's token
+    /// transfer history.
+    async fn display_token_balances(&self) -> Result<(), WalletError> {
+        let native_balance = get_balance(&self.provider, &self.wallet).await?;
+        let token_balances = get_token_balances(
+            &self.provider,
+            &self.store,
+            self.chain,
+            &self.wallet,
+            &self.tokens,
+        )
+        .await?;
+
+        println!(
+            "{} {}",
+            native_balance.to_ether_string(),
+            self.chain.native_symbol()
+        );
+        for token_balance in &token_balances {
+            println!("{}", token_balance);
+        }
+        if token_balances.is_empty() {
+            println!("No token balances found");
+        }
+        println!();
+
+        Ok(())
+    }
+
+    /// Displays the NFTs the wallet currently holds, followed by the
+    /// transfers through which it received NFTs.
+    async fn display_nfts(&self) -> Result<(), WalletError> {
+        let transfers =
+            get_nft_transfers(&self.provider, &self.store, self.chain, &self.wallet).await?;
+        let holdings = nft_holdings(&transfers);
+
+        println!("NFTs held on {}:", self.chain);
+        for holding in &holdings {
+            println!("{}", holding);
+        }
+        if holdings.is_empty() {
+            println!("No NFTs found");
+        }
+        println!();
+
+        let acquisitions: Vec<_> = transfers
+            .iter()
+            .filter(|transfer| transfer.direction == Direction::In)
+            .collect();
+        if !acquisitions.is_empty() {
+            println!("Acquisition history:");
+            for transfer in acquisitions {
+                transfer.display_detailed();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Displays a list of the wallet's transactions in detailed format, valued
+    /// in the configured currencies, followed by its ERC-20 token transfers.
     ///
     /// # Arguments
-    /// * `wallet` - Wallet address as a string slice.
-    async fn display_transactions(&self) -> Result<(), WalletError> {
-        let transactions_offset = input::get_transaction_offset();
-        let transactions: Vec<Transaction> =
-            get_transactions(&self.wallet, transactions_offset).await?;
+    /// * `transactions_offset` - Number of transactions and token transfers to display.
+    async fn display_transactions(&self, transactions_offset: i32) -> Result<(), WalletError> {
+        let mut transactions: Vec<Transaction> = get_transactions(
+            &self.provider,
+            &self.store,
+            self.chain,
+            &self.wallet,
+            transactions_offset,
+        )
+        .await?;
+        utils::value_transactions(
+            &self.store,
+            &self.prices,
+            self.chain,
+            &mut transactions,
+            &self.currencies,
+        )
+        .await?;
+        let addresses: Vec<&str> = transactions
+            .iter()
+            .flat_map(|tx| [tx.from.as_str(), tx.to.as_str()])
+            .collect();
+        let names = self.ens.reverse_resolve_all(&addresses).await;
         for tx in transactions {
-            tx.display_detailed();
+            tx.display_detailed(&names);
+        }
+
+        let token_transfers = get_token_transfers(
+            &self.store,
+            self.chain,
+            &self.wallet,
+            Some(transactions_offset as usize),
+        )
+        .await?;
+        for transfer in token_transfers {
+            transfer.display_detailed();
         }
         Ok(())
     }
 
-    /// Displays the average gas used in recent transactions for a wallet.
-    ///
-    /// The number of transactions is determined by user input.
+    /// Displays the total and average fees paid in recent transactions of a wallet.
     ///
     /// # Arguments
-    /// * `wallet` - Wallet address as a string slice.
-    async fn display_average_gas(&self) -> Result<(), WalletError> {
-        let transactions_offset = input::get_transaction_offset();
-        let average_gas = get_average_gas(&self.wallet, transactions_offset).await?;
-        println!(
-            "The average gas for the last transactions was: {}\n",
-            average_gas
-        );
+    /// * `transactions_offset` - Number of transactions to include.
+    async fn display_fees(&self, transactions_offset: i32) -> Result<(), WalletError> {
+        let fees = get_fee_summary(
+            &self.provider,
+            &self.store,
+            &self.prices,
+            self.chain,
+            &self.wallet,
+            transactions_offset,
+            &self.currencies,
+        )
+        .await?;
+        println!("{}", fees);
         Ok(())
     }
 
@@ -80,8 +193,135 @@ impl wallet_traits::WalletDisplay for EtherAccount {
     /// # Arguments
     /// * `wallet` - Wallet address as a string slice.
     async fn display_statistics(&self) -> Result<(), WalletError> {
-        let stats = utils::generate_statistics(&self.wallet).await?;
+        let stats =
+            utils::generate_statistics(&self.provider, &self.store, self.chain, &self.wallet)
+                .await?;
         print!("{}", stats);
         Ok(())
     }
+
+    /// Displays realized and unrealized gains of the native currency in the
+    /// first configured currency, using the configured lot matching method.
+    async fn display_cost_basis(&self) -> Result<(), WalletError> {
+        let currency = self.currencies.first().cloned().unwrap_or_default();
+        let report = utils::get_cost_basis(
+            &self.provider,
+            &self.store,
+            &self.prices,
+            self.chain,
+            &self.wallet,
+            &currency,
+            self.cost_basis_method,
+        )
+        .await?;
+
+        for disposal in &report.disposals {
+            println!(
+                "{} disposed of {} {}{} for {} (cost {}, gain {})",
+                disposal.date,
+                disposal.quantity.to_ether_string(),
+                self.chain.native_symbol(),
+                if disposal.fee { " as fee" } else { "" },
+                report.fiat(disposal.proceeds),
+                report.fiat(disposal.cost_basis),
+                report.fiat(disposal.gain()),
+            );
+        }
+        print!("{}", report);
+        if report.unpriced > 0 {
+            println!(
+                "{} transactions had no historical price and were valued at zero",
+                report.unpriced
+            );
+        }
+        println!();
+
+        Ok(())
+    }
+
+    /// Displays the addresses the wallet transacts with the most, with their
+    /// primary ENS names.
+    ///
+    /// # Arguments
+    /// * `sort` - Order of the counterparties.
+    /// * `limit` - Number of counterparties to display.
+    async fn display_counterparties(
+        &self,
+        sort: CounterpartySort,
+        limit: usize,
+    ) -> Result<(), WalletError> {
+        let counterparties = utils::get_counterparties(
+            &self.provider,
+            &self.store,
+            self.chain,
+            &self.wallet,
+            sort,
+            Some(limit),
+        )
+        .await?;
+
+        let addresses: Vec<&str> = counterparties
+            .iter()
+            .map(|counterparty| counterparty.address.as_str())
+            .collect();
+        let names = self.ens.reverse_resolve_all(&addresses).await;
+
+        println!("Top counterparties on {}:", self.chain);
+        for counterparty in &counterparties {
+            counterparty.display_compact(self.chain, &names);
+        }
+        if counterparties.is_empty() {
+            println!("No counterparties found");
+        }
+        println!();
+        Ok(())
+    }
+
+    /// Displays the wallet's balance over time, rebuilt from its history.
+    ///
+    /// # Arguments
+    /// * `interval` - Spacing of the points.
+    async fn display_balance_history(&self, interval: SamplingInterval) -> Result<(), WalletError> {
+        let timeline = utils::get_balance_timeline(
+            &self.provider,
+            &self.store,
+            self.chain,
+            &self.wallet,
+            interval,
+        )
+        .await?;
+        println!("{}", timeline);
+        Ok(())
+    }
+
+    /// Draws a chart of the wallet's complete history in the console.
+    ///
+    /// # Arguments
+    /// * `kind` - Chart to draw.
+    /// * `interval` - Spacing of the points of the balance, volume and gas charts.
+    async fn display_chart(
+        &self,
+        kind: ChartKind,
+        interval: SamplingInterval,
+    ) -> Result<(), WalletError> {
+        let transactions =
+            utils::get_all_transactions(&self.provider, &self.store, self.chain, &self.wallet)
+                .await?;
+        let timeline = balance_history::balance_timeline(
+            self.chain,
+            &self.wallet,
+            &transactions,
+            interval,
+            Utc::now().date_naive(),
+        );
+
+        let chart = match kind {
+            ChartKind::Balance => charts::balance_chart(&timeline),
+            ChartKind::Volume => charts::volume_chart(&timeline),
+            ChartKind::Values => charts::values_chart(self.chain, &transactions),
+            ChartKind::Gas => charts::gas_chart(&timeline, &transactions),
+        };
+        println!("{}", chart);
+        Ok(())
+    }
 }
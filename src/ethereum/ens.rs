@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::abi::{self, eth_call};
+use super::provider::Provider;
+use crate::{address::Address, errors::WalletError};
+
+/// ENS registry, deployed at the same address on Ethereum mainnet and testnets.
+const ENS_REGISTRY: &str = "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+
+/// Selector of `resolver(bytes32)` on the registry.
+const RESOLVER_SELECTOR: &str = "0x0178b8bf";
+
+/// Selector of `addr(bytes32)` on a resolver.
+const ADDR_SELECTOR: &str = "0x3b3b57de";
+
+/// Selector of `name(bytes32)` on a reverse resolver.
+const NAME_SELECTOR: &str = "0x691f3431";
+
+/// Address returned by the registry and resolvers for unset records.
+const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+
+/// Resolves ENS names to addresses and addresses to their primary names.
+///
+/// ENS lives on Ethereum mainnet, so the resolver is given a mainnet
+/// provider whatever chain the wallet is tracked on. Lookups, including
+/// failed ones, are cached for the lifetime of the resolver.
+pub struct EnsResolver {
+    provider: Provider,
+    names: Mutex<HashMap<String, Option<Address>>>,
+    addresses: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl EnsResolver {
+    /// Creates a resolver querying the ENS contracts through `provider`.
+    pub fn new(provider: Provider) -> Self {
+        EnsResolver {
+            provider,
+            names: Mutex::new(HashMap::new()),
+            addresses: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns whether the input looks like an ENS name rather than an address.
+    pub fn is_name(input: &str) -> bool {
+        let input = input.trim();
+        input.contains('.') && !input.starts_with("0x")
+    }
+
+    /// Resolves an ENS name, such as `vitalik.eth`, to the address it points to.
+    ///
+    /// # Errors
+    /// Returns [`WalletError::InvalidAddress`] if the name has no resolver or no address record.
+    pub async fn resolve(&self, name: &str) -> Result<Address, WalletError> {
+        let name = name.trim().to_lowercase();
+        if let Some(cached) = self.names.lock().expect("ENS cache poisoned").get(&name) {
+            return cached.clone().ok_or_else(|| unresolved(&name));
+        }
+
+        let address = self.lookup_address(&name).await?;
+        self.names
+            .lock()
+            .expect("ENS cache poisoned")
+            .insert(name.clone(), address.clone());
+
+        address.ok_or_else(|| unresolved(&name))
+    }
+
+    /// Returns the primary ENS name of an address, if it has one.
+    ///
+    /// The reverse record is only trusted when the name resolves back to the
+    /// same address, as anyone can set an arbitrary reverse record. Lookup
+    /// failures are treated as "no name" so display never fails because of ENS.
+    pub async fn reverse_resolve(&self, address: &str) -> Option<String> {
+        let address = address.to_lowercase();
+        if let Some(cached) = self
+            .addresses
+            .lock()
+            .expect("ENS cache poisoned")
+            .get(&address)
+        {
+            return cached.clone();
+        }
+
+        let name = match self.lookup_name(&address).await {
+            Ok(Some(name)) => match self.resolve(&name).await {
+                Ok(resolved) if resolved.as_str() == address => Some(name),
+                _ => None,
+            },
+            _ => None,
+        };
+        self.addresses
+            .lock()
+            .expect("ENS cache poisoned")
+            .insert(address, name.clone());
+
+        name
+    }
+
+    /// Reverse-resolves every address of a list.
+    ///
+    /// # Returns
+    /// * The primary names found, keyed by lowercase address.
+    pub async fn reverse_resolve_all(&self, addresses: &[&str]) -> HashMap<String, String> {
+        let mut names = HashMap::new();
+        for &address in addresses {
+            if address.is_empty() || names.contains_key(&address.to_lowercase()) {
+                continue;
+            }
+            if let Some(name) = self.reverse_resolve(address).await {
+                names.insert(address.to_lowercase(), name);
+            }
+        }
+        names
+    }
+
+    /// Reads the `addr` record of a name from its resolver.
+    async fn lookup_address(&self, name: &str) -> Result<Option<Address>, WalletError> {
+        let node = namehash(name);
+        let Some(resolver) = self.resolver(&node).await? else {
+            return Ok(None);
+        };
+
+        let data = format!("{}{}", ADDR_SELECTOR, node);
+        let address = abi::decode_address(&eth_call(&self.provider, &resolver, &data).await?)?;
+        if address == ZERO_ADDRESS {
+            return Ok(None);
+        }
+
+        Address::parse(&address).map(Some)
+    }
+
+    /// Reads the `name` record of an address from its reverse resolver.
+    async fn lookup_name(&self, address: &str) -> Result<Option<String>, WalletError> {
+        let node = namehash(&format!(
+            "{}.addr.reverse",
+            address.trim_start_matches("0x")
+        ));
+        let Some(resolver) = self.resolver(&node).await? else {
+            return Ok(None);
+        };
+
+        let data = format!("{}{}", NAME_SELECTOR, node);
+        let name = abi::decode_string(&eth_call(&self.provider, &resolver, &data).await?)?;
+
+        Ok(Some(name).filter(|name| !name.is_empty()))
+    }
+
+    /// Returns the resolver registered for a node, or `None` if there is none.
+    async fn resolver(&self, node: &str) -> Result<Option<String>, WalletError> {
+        let data = format!("{}{}", RESOLVER_SELECTOR, node);
+        let resolver = abi::decode_address(&eth_call(&self.provider, ENS_REGISTRY, &data).await?)?;
+
+        Ok(Some(resolver).filter(|resolver| resolver != ZERO_ADDRESS))
+    }
+}
+
+/// Computes the ENS `namehash` of a name as 64 hex characters.
+///
+/// Labels are lowercased but otherwise taken as is; full UTS-46
+/// normalization is not applied.
+fn namehash(name: &str) -> String {
+    let mut node = [0u8; 32];
+    for label in name.rsplit('.').filter(|label| !label.is_empty()) {
+        let mut joined = node.to_vec();
+        joined.extend_from_slice(&abi::keccak256(label.to_lowercase().as_bytes()));
+        node = abi::keccak256(&joined);
+    }
+
+    node.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Builds the error returned for names without an address record.
+fn unresolved(name: &str) -> WalletError {
+    WalletError::InvalidAddress {
+        address: name.to_string(),
+        reason: "the ENS name does not resolve to an address".to_string(),
+    }
+}
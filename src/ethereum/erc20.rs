@@ -0,0 +1,139 @@
+use primitive_types::U256;
+use serde_json::Value;
+
+use super::abi::{self, eth_call};
+use super::chain::Chain;
+use super::etherscan;
+use super::provider::Provider;
+use crate::{
+    address::Address,
+    date_utils,
+    enums::Direction,
+    errors::WalletError,
+    store::{TOKEN_TRANSFERS_KIND, TransactionStore},
+    structs::{TokenBalance, TokenTransfer},
+};
+
+/// Selector of `balanceOf(address)`.
+const BALANCE_OF_SELECTOR: &str = "0x70a08231";
+
+/// Selector of `decimals()`.
+const DECIMALS_SELECTOR: &str = "0x313ce567";
+
+/// Selector of `symbol()`.
+pub const SYMBOL_SELECTOR: &str = "0x95d89b41";
+
+/// Reads the balance, decimals and symbol of an ERC-20 token for a wallet.
+///
+/// # Arguments
+/// * `provider` - JSON-RPC provider of the chain the token lives on.
+/// * `token` - Token contract address.
+/// * `wallet` - Wallet address.
+pub async fn get_token_balance(
+    provider: &Provider,
+    token: &Address,
+    wallet: &Address,
+) -> Result<TokenBalance, WalletError> {
+    let balance_data = format!(
+        "{}{}",
+        BALANCE_OF_SELECTOR,
+        abi::encode_address(wallet.as_str())
+    );
+    let token = token.as_str();
+    let balance = abi::decode_uint(&eth_call(provider, token, &balance_data).await?)?;
+
+    let decimals = abi::decode_u8(&eth_call(provider, token, DECIMALS_SELECTOR).await?)?;
+    let symbol = abi::decode_string(&eth_call(provider, token, SYMBOL_SELECTOR).await?)?;
+
+    Ok(TokenBalance {
+        contract: token.to_string(),
+        symbol,
+        decimals,
+        balance,
+    })
+}
+
+/// Returns the ERC-20 token transfers of a wallet, oldest first.
+///
+/// The local cache is synced first, then read.
+///
+/// # Arguments
+/// * `store` - Local transaction cache.
+/// * `chain` - Chain to query.
+/// * `wallet` - Wallet address.
+/// * `limit` - Maximum number of transfers to return, or `None` for all of them.
+pub async fn get_token_transfers(
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+    limit: Option<usize>,
+) -> Result<Vec<TokenTransfer>, WalletError> {
+    sync_token_transfers(store, chain, wallet).await?;
+    store.load_token_transfers(chain, wallet, limit)
+}
+
+/// Brings the cached token transfer history of a wallet up to date.
+///
+/// Uses Etherscan's `tokentx` list, walked from the block after the last sync.
+///
+/// # Returns
+/// * The number of token transfers added to the cache.
+pub async fn sync_token_transfers(
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+) -> Result<usize, WalletError> {
+    let start_block = store
+        .last_synced_block(chain, wallet, TOKEN_TRANSFERS_KIND)?
+        .map_or(0, |block| block + 1);
+
+    let rows =
+        etherscan::fetch_full_history(chain, TOKEN_TRANSFERS_KIND, wallet, start_block).await?;
+    let transfers = rows
+        .iter()
+        .map(|row| parse_token_transfer(chain, wallet, row))
+        .collect::<Result<Vec<TokenTransfer>, WalletError>>()?;
+
+    let last_block = transfers
+        .last()
+        .map_or(start_block.saturating_sub(1), |transfer| {
+            transfer.block_number
+        });
+    store.save_token_transfers(chain, wallet, &transfers, last_block)
+}
+
+/// Parses a single Etherscan `tokentx` row into a [`TokenTransfer`].
+fn parse_token_transfer(
+    chain: Chain,
+    wallet: &Address,
+    row: &Value,
+) -> Result<TokenTransfer, WalletError> {
+    let field = |name: &str| {
+        row[name].as_str().ok_or(WalletError::Missing {
+            field: name.to_string(),
+        })
+    };
+
+    let from = field("from")?.to_string();
+    let to = field("to")?.to_string();
+    let timestamp = field("timeStamp")?.to_string();
+    let amount = field("value")?;
+
+    Ok(TokenTransfer {
+        chain,
+        hash: field("hash")?.to_string(),
+        log_index: field("logIndex")?.parse::<u64>()?,
+        block_number: etherscan::block_number(row)?,
+        timestamp: timestamp.parse::<u64>()?,
+        contract: field("contractAddress")?.to_lowercase(),
+        symbol: field("tokenSymbol")?.to_string(),
+        decimals: field("tokenDecimal")?.parse::<u8>().unwrap_or(0),
+        direction: Direction::of(wallet.as_str(), &from, &to),
+        from,
+        to,
+        amount: U256::from_dec_str(amount).map_err(|_| WalletError::AmountParse {
+            value: amount.to_string(),
+        })?,
+        date: date_utils::epoch_converter(timestamp),
+    })
+}
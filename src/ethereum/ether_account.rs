@@ -1,3 +1,28 @@
+use super::chain::Chain;
+use super::ens::EnsResolver;
+use super::provider::Provider;
+use crate::{
+    address::Address, cost_basis::CostBasisMethod, currency::Currency, prices::PriceOracle,
+    store::TransactionStore,
+};
+
+/// A wallet address tracked on one EVM chain.
+///
+/// The same address can be tracked on any supported [`Chain`]; the provider
+/// must point at a node of that chain.
 pub struct EtherAccount {
-    pub wallet: String,
+    pub wallet: Address,
+    pub chain: Chain,
+    /// ERC-20 token contracts to show in addition to those discovered from history.
+    pub tokens: Vec<Address>,
+    pub provider: Provider,
+    /// ENS resolver, always backed by an Ethereum mainnet provider.
+    pub ens: EnsResolver,
+    /// Fiat price sources with fallback and caching.
+    pub prices: PriceOracle,
+    /// Fiat currencies balances and values are shown in.
+    pub currencies: Vec<Currency>,
+    /// Lot matching method used for gains.
+    pub cost_basis_method: CostBasisMethod,
+    pub store: TransactionStore,
 }
@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use reqwest::Client;
+use serde_json::Value;
+
+use super::chain::Chain;
+use crate::{address::Address, errors::WalletError};
+
+/// Base URL of the Etherscan v2 API, shared by every supported chain.
+const ETHERSCAN_API_URL: &str = "https://api.etherscan.io/v2/api";
+
+/// Number of rows requested per page when walking a full history.
+const PAGE_SIZE: u32 = 1_000;
+
+/// Etherscan rejects requests where `page * offset` exceeds this many rows.
+const RESULT_WINDOW: u32 = 10_000;
+
+/// Highest block number accepted by Etherscan as an open-ended `endblock`.
+const LATEST_BLOCK: u64 = 99_999_999;
+
+/// How many times a rate-limited request is retried before giving up.
+const RATE_LIMIT_RETRIES: u32 = 5;
+
+/// Delay between retries of a rate-limited request.
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Reads the Etherscan API key from the `ETHERSCAN_KEY` environment variable.
+fn api_key() -> Result<String, WalletError> {
+    std::env::var("ETHERSCAN_KEY").map_err(|_| WalletError::Missing {
+        field: "ETHERSCAN_KEY".to_string(),
+    })
+}
+
+/// Fetches one page of an Etherscan `account` module list endpoint.
+///
+/// # Arguments
+/// * `chain` - Chain to query.
+/// * `action` - List action, e.g. `txlist`.
+/// * `wallet` - Wallet address.
+/// * `start_block` - First block to include.
+/// * `page` - 1-based page number.
+/// * `offset` - Number of rows per page.
+///
+/// # Returns
+/// * The raw rows of the page, sorted by ascending block number.
+pub async fn fetch_account_page(
+    chain: Chain,
+    action: &str,
+    wallet: &Address,
+    start_block: u64,
+    page: u32,
+    offset: u32,
+) -> Result<Vec<Value>, WalletError> {
+    let client = Client::new();
+    let etherscan_token = api_key()?;
+
+    let url = format!(
+        concat!(
+            "{}?",
+            "chainid={}",
+            "&module=account",
+            "&action={}",
+            "&address={}",
+            "&startblock={}",
+            "&endblock={}",
+            "&page={}",
+            "&offset={}",
+            "&sort=asc",
+            "&apikey={}"
+        ),
+        ETHERSCAN_API_URL,
+        chain.chain_id(),
+        action,
+        wallet.as_str(),
+        start_block,
+        LATEST_BLOCK,
+        page,
+        offset,
+        etherscan_token
+    );
+
+    let mut retries = 0;
+    loop {
+        let response = client.get(&url).send().await?.json::<Value>().await?;
+
+        match &response["result"] {
+            Value::Array(rows) => return Ok(rows.clone()),
+            Value::String(message) if message.contains("rate limit") => {
+                if retries == RATE_LIMIT_RETRIES {
+                    return Err(WalletError::Etherscan {
+                        message: message.clone(),
+                    });
+                }
+                retries += 1;
+                tokio::time::sleep(RATE_LIMIT_BACKOFF).await;
+            }
+            Value::String(message) => {
+                return Err(WalletError::Etherscan {
+                    message: message.clone(),
+                });
+            }
+            _ => {
+                return Err(WalletError::Missing {
+                    field: "result".to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// Fetches every row of an Etherscan `account` module list endpoint.
+///
+/// Etherscan only serves the first [`RESULT_WINDOW`] rows of a query, so the
+/// history is walked in block ranges: once a window is exhausted, the query
+/// restarts from the last block seen. Rows of that boundary block are dropped
+/// first so the restarted query returns them exactly once.
+///
+/// # Arguments
+/// * `chain` - Chain to query.
+/// * `action` - List action, e.g. `txlist`.
+/// * `wallet` - Wallet address.
+/// * `start_block` - First block to include.
+pub async fn fetch_full_history(
+    chain: Chain,
+    action: &str,
+    wallet: &Address,
+    start_block: u64,
+) -> Result<Vec<Value>, WalletError> {
+    let mut rows: Vec<Value> = Vec::new();
+    let mut start_block = start_block;
+
+    loop {
+        let window_start = rows.len();
+        let mut window_full = true;
+
+        for page in 1..=RESULT_WINDOW / PAGE_SIZE {
+            let page_rows =
+                fetch_account_page(chain, action, wallet, start_block, page, PAGE_SIZE).await?;
+            let page_len = page_rows.len();
+            rows.extend(page_rows);
+
+            if page_len < PAGE_SIZE as usize {
+                window_full = false;
+                break;
+            }
+        }
+
+        if !window_full {
+            return Ok(rows);
+        }
+
+        let boundary_block = block_number(&rows[rows.len() - 1])?;
+        if boundary_block == start_block {
+            return Err(WalletError::Etherscan {
+                message: format!(
+                    "block {} holds more than {} rows for this address",
+                    boundary_block, RESULT_WINDOW
+                ),
+            });
+        }
+
+        let mut keep = rows.len();
+        while keep > window_start && block_number(&rows[keep - 1])? == boundary_block {
+            keep -= 1;
+        }
+        rows.truncate(keep);
+        start_block = boundary_block;
+    }
+}
+
+/// Reads the `blockNumber` field of an Etherscan row.
+pub fn block_number(row: &Value) -> Result<u64, WalletError> {
+    Ok(row["blockNumber"]
+        .as_str()
+        .ok_or(WalletError::Missing {
+            field: "blockNumber".to_string(),
+        })?
+        .parse::<u64>()?)
+}
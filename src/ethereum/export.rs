@@ -1,62 +1,622 @@
-use std::fs::File;
+use std::fs;
+use std::path::Path;
 
 use async_trait::async_trait;
+use chrono::Utc;
 use csv::Writer;
 
 use crate::{
+    amount::Wei,
+    balance_history::{self, SamplingInterval},
+    cli::DEFAULT_COUNTERPARTY_LIMIT,
+    cost_basis::{self, CostBasisReport},
+    currency::{Currency, FiatAmount},
+    enums::CounterpartySort,
     errors::WalletError,
-    ethereum::utils::{self, get_transactions},
+    ethereum::utils::{self, get_all_transactions, get_balance},
+    structs::{
+        Counterparty, NftHolding, NftTransfer, NotableTransfer, Statistics, TokenTransfer,
+        Transaction,
+    },
+    tax::{self, ExportFormat, TaxProfile},
     wallet_traits::WalletExport,
 };
 
+use super::erc20::get_token_transfers;
 use super::ether_account::EtherAccount;
+use super::nft::{get_nft_transfers, nft_holdings};
 
 #[async_trait]
 impl WalletExport for EtherAccount {
-    /// Exports wallet statistics and transactions to two CSV files:
+    /// Exports the wallet history to CSV files inside `out_dir`, in the layout of `format`.
+    ///
+    /// The directory is created if it does not exist yet.
+    async fn export_to_csv(&self, out_dir: &Path, format: ExportFormat) -> Result<(), WalletError> {
+        fs::create_dir_all(out_dir)?;
+
+        match format.profile() {
+            Some(profile) => self.export_tax_csv(out_dir, profile.as_ref()).await,
+            None => self.export_standard_csv(out_dir).await,
+        }
+    }
+
+    /// Exports the wallet's balance history to `path`, as JSON if the file
+    /// name ends in `.json` and as CSV otherwise.
+    ///
+    /// # Arguments
+    /// * `path` - File to write. Its directory is created if needed.
+    /// * `interval` - Spacing of the points.
+    async fn export_balance_history(
+        &self,
+        path: &Path,
+        interval: SamplingInterval,
+    ) -> Result<(), WalletError> {
+        let timeline = utils::get_balance_timeline(
+            &self.provider,
+            &self.store,
+            self.chain,
+            &self.wallet,
+            interval,
+        )
+        .await?;
+
+        create_parent_dir(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => {
+                timeline.write_json(path)?
+            }
+            _ => timeline.write_csv(path)?,
+        }
+
+        println!(
+            "Exported {} {} balance points for {} to {}",
+            timeline.points.len(),
+            interval,
+            &self.wallet,
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Exports the top counterparties of the wallet to a CSV file.
+    ///
+    /// # Arguments
+    /// * `path` - File to write. Its directory is created if needed.
+    /// * `sort` - Order of the counterparties, largest first.
+    /// * `limit` - Number of counterparties to include.
+    async fn export_counterparties(
+        &self,
+        path: &Path,
+        sort: CounterpartySort,
+        limit: usize,
+    ) -> Result<(), WalletError> {
+        let counterparties = utils::get_counterparties(
+            &self.provider,
+            &self.store,
+            self.chain,
+            &self.wallet,
+            sort,
+            Some(limit),
+        )
+        .await?;
+
+        create_parent_dir(path)?;
+        write_counterparties_csv(path, &counterparties)?;
+
+        println!(
+            "Exported {} counterparties of {} to {}",
+            counterparties.len(),
+            &self.wallet,
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+impl EtherAccount {
+    /// Exports wallet statistics, transactions, token transfers and NFTs to CSV files inside `out_dir`:
     /// - `statistics.csv`
+    /// - `cost_basis.csv`, when a spot price is available
     /// - `transactions.csv`
-    async fn export_to_csv(&self) -> Result<(), WalletError> {
-        let stats = utils::generate_statistics(&self.wallet).await?;
-        let tx_count: i32 = stats.total_transactions.parse()?;
-        let transactions = get_transactions(&self.wallet, tx_count).await?;
-
-        let stats_file = File::create("statistics.csv")?;
-        let mut stats_writer = Writer::from_writer(stats_file);
-
-        stats_writer.write_record(&[
-            "Address",
-            "Total Transactions",
-            "Average Gas",
-            "Average ETH",
-            "First Transaction",
-        ])?;
+    /// - `counterparties.csv`, with contract checks for the top counterparties only
+    /// - `balance_history.csv`, with one point per day
+    /// - `token_transfers.csv`
+    /// - `nft_transfers.csv`
+    /// - `nft_holdings.csv`
+    async fn export_standard_csv(&self, out_dir: &Path) -> Result<(), WalletError> {
+        let mut transactions =
+            get_all_transactions(&self.provider, &self.store, self.chain, &self.wallet).await?;
+        utils::value_transactions(
+            &self.store,
+            &self.prices,
+            self.chain,
+            &mut transactions,
+            &self.currencies,
+        )
+        .await?;
+        let token_transfers =
+            get_token_transfers(&self.store, self.chain, &self.wallet, None).await?;
+        let nft_transfers =
+            get_nft_transfers(&self.provider, &self.store, self.chain, &self.wallet).await?;
+        let holdings = nft_holdings(&nft_transfers);
+        let balance = get_balance(&self.provider, &self.wallet).await?;
+        let stats = utils::statistics_from_transactions(
+            self.chain,
+            &self.wallet,
+            &transactions,
+            &token_transfers,
+        )?;
 
-        stats_writer.write_record(&[
-            stats.address,
-            stats.total_transactions,
-            stats.average_gas.to_string(),
-            stats.average_eth.to_string(),
-            stats.first_transaction,
-        ])?;
-
-        stats_writer.flush()?;
+        // A missing price leaves its column empty rather than failing the export.
+        let mut fiat_balances = Vec::new();
+        for currency in &self.currencies {
+            match self
+                .prices
+                .spot_price(self.chain.native_symbol(), currency.code())
+                .await
+            {
+                Ok(rate) => fiat_balances.push(FiatAmount {
+                    currency: currency.clone(),
+                    amount: rate * balance.to_ether_f64(),
+                }),
+                Err(error) => eprintln!("Exporting without {} balance: {}", currency, error),
+            }
+        }
 
-        let tx_file = File::create("transactions.csv")?;
-        let mut tx_writer = Writer::from_writer(tx_file);
+        // Gains need a spot price; without one the cost basis file is skipped.
+        let currency = self.currencies.first().cloned().unwrap_or_default();
+        let cost_basis = match self
+            .prices
+            .spot_price(self.chain.native_symbol(), currency.code())
+            .await
+        {
+            Ok(spot_price) => Some(cost_basis::compute_cost_basis(
+                self.cost_basis_method,
+                &transactions,
+                &currency,
+                balance,
+                spot_price,
+            )),
+            Err(error) => {
+                eprintln!("Exporting without cost basis: {}", error);
+                None
+            }
+        };
 
-        tx_writer.write_record(&["From", "To", "Gas", "Quantity", "Date"])?;
+        // Every counterparty is listed, but only the top ones are checked for
+        // code, as the counterparties view does, to keep the export to a few requests.
+        let mut counterparties = utils::counterparties(&transactions, CounterpartySort::default());
+        let checked = counterparties.len().min(DEFAULT_COUNTERPARTY_LIMIT);
+        utils::check_contracts(
+            &self.provider,
+            &self.store,
+            self.chain,
+            &mut counterparties[..checked],
+        )
+        .await?;
 
-        for tx in transactions {
-            tx_writer.write_record(&[tx.from, tx.to, tx.gas, tx.quantity.to_string(), tx.date])?;
+        write_statistics_csv(
+            &out_dir.join("statistics.csv"),
+            &stats,
+            balance,
+            &self.currencies,
+            &fiat_balances,
+            &currency,
+            cost_basis.as_ref(),
+        )?;
+        if let Some(report) = &cost_basis {
+            write_cost_basis_csv(&out_dir.join("cost_basis.csv"), report, &currency)?;
         }
+        balance_history::balance_timeline(
+            self.chain,
+            &self.wallet,
+            &transactions,
+            SamplingInterval::Daily,
+            Utc::now().date_naive(),
+        )
+        .write_csv(&out_dir.join("balance_history.csv"))?;
+        write_transactions_csv(
+            &out_dir.join("transactions.csv"),
+            &transactions,
+            &self.currencies,
+        )?;
+        write_counterparties_csv(&out_dir.join("counterparties.csv"), &counterparties)?;
+        write_token_transfers_csv(&out_dir.join("token_transfers.csv"), &token_transfers)?;
+        write_nft_transfers_csv(&out_dir.join("nft_transfers.csv"), &nft_transfers)?;
+        write_nft_holdings_csv(&out_dir.join("nft_holdings.csv"), &holdings)?;
 
-        tx_writer.flush()?;
+        println!(
+            "Exported statistics, transactions, token transfers and NFTs for {} to CSV files in {}",
+            &self.wallet,
+            out_dir.display()
+        );
+        Ok(())
+    }
+
+    /// Exports native transactions and token transfers to a single CSV file
+    /// that `profile`'s tax tool can import.
+    ///
+    /// Fiat values are given in the first configured currency.
+    async fn export_tax_csv(
+        &self,
+        out_dir: &Path,
+        profile: &dyn TaxProfile,
+    ) -> Result<(), WalletError> {
+        let mut transactions =
+            get_all_transactions(&self.provider, &self.store, self.chain, &self.wallet).await?;
+        utils::value_transactions(
+            &self.store,
+            &self.prices,
+            self.chain,
+            &mut transactions,
+            &self.currencies,
+        )
+        .await?;
+        let token_transfers =
+            get_token_transfers(&self.store, self.chain, &self.wallet, None).await?;
+
+        let currency = self.currencies.first().cloned().unwrap_or_default();
+        let entries = tax::tax_entries(self.chain, &transactions, &token_transfers, &currency);
+
+        let path = out_dir.join(profile.file_name());
+        tax::write_tax_csv(profile, &entries, &path)?;
 
         println!(
-            "Exported statistics and transactions for {} to CSV files",
-            &self.wallet
+            "Exported {} entries for {} in {} format to {}",
+            entries.len(),
+            &self.wallet,
+            profile.name(),
+            path.display()
         );
         Ok(())
     }
 }
+
+/// Creates the directory `path` is written to, if it has one.
+fn create_parent_dir(path: &Path) -> Result<(), WalletError> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(())
+}
+
+/// Writes the wallet statistics to `path` as a header and a single row.
+///
+/// # Arguments
+/// * `currencies` - Configured currencies, one balance column each.
+/// * `fiat_balances` - Balance in the currencies that have a spot price.
+/// * `currency` - Currency of the gain columns.
+/// * `cost_basis` - Gains report, or `None` to leave the gain columns empty.
+fn write_statistics_csv(
+    path: &Path,
+    stats: &Statistics,
+    balance: Wei,
+    currencies: &[Currency],
+    fiat_balances: &[FiatAmount],
+    currency: &Currency,
+    cost_basis: Option<&CostBasisReport>,
+) -> Result<(), WalletError> {
+    let mut writer = Writer::from_path(path)?;
+
+    let mut header: Vec<String> = [
+        "Chain",
+        "Address",
+        "Total Transactions",
+        "Average Gas",
+        "Average Value",
+        "Median Value",
+        "P90 Value",
+        "Largest Inflow",
+        "Largest Inflow Hash",
+        "Largest Outflow",
+        "Largest Outflow Hash",
+        "Total Inflow",
+        "Total Outflow",
+        "Net Flow",
+        "First Transaction",
+        "Last Activity",
+        "Active Days",
+        "Unique Counterparties",
+        "Total Token Transfers",
+        "Distinct Tokens",
+        "Balance",
+    ]
+    .map(String::from)
+    .to_vec();
+    let notable = |transfer: &Option<NotableTransfer>| match transfer {
+        Some(transfer) => (transfer.quantity.to_ether_string(), transfer.hash.clone()),
+        None => (String::new(), String::new()),
+    };
+    let (largest_inflow, largest_inflow_hash) = notable(&stats.largest_inflow);
+    let (largest_outflow, largest_outflow_hash) = notable(&stats.largest_outflow);
+    let mut record = vec![
+        stats.chain.to_string(),
+        stats.address.to_string(),
+        stats.total_transactions.to_string(),
+        stats.average_gas.to_string(),
+        stats.average_eth.to_ether_string(),
+        stats.median_value.to_ether_string(),
+        stats.p90_value.to_ether_string(),
+        largest_inflow,
+        largest_inflow_hash,
+        largest_outflow,
+        largest_outflow_hash,
+        stats.total_inflow.to_ether_string(),
+        stats.total_outflow.to_ether_string(),
+        stats.net_flow(),
+        stats.first_transaction.to_string(),
+        stats.last_activity.to_string(),
+        stats.active_days.to_string(),
+        stats.unique_counterparties.to_string(),
+        stats.total_token_transfers.to_string(),
+        stats.distinct_tokens.to_string(),
+        balance.to_ether_string(),
+    ];
+
+    header.extend(
+        currencies
+            .iter()
+            .map(|currency| format!("Balance {}", currency)),
+    );
+    record.extend(fiat_columns(currencies, fiat_balances));
+
+    header.push(format!("Realized Gain {}", currency));
+    header.push(format!("Unrealized Gain {}", currency));
+    match cost_basis {
+        Some(report) => {
+            record.push(currency.format_plain(report.realized_gain()));
+            record.push(currency.format_plain(report.unrealized_gain()));
+        }
+        None => record.extend([String::new(), String::new()]),
+    }
+
+    writer.write_record(&header)?;
+    writer.write_record(&record)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes every disposal of a cost basis report to `path`, amounts in `currency`.
+fn write_cost_basis_csv(
+    path: &Path,
+    report: &CostBasisReport,
+    currency: &Currency,
+) -> Result<(), WalletError> {
+    let mut writer = Writer::from_path(path)?;
+
+    writer.write_record([
+        "Hash".to_string(),
+        "Date".to_string(),
+        "Method".to_string(),
+        "Kind".to_string(),
+        "Quantity".to_string(),
+        format!("Proceeds {}", currency),
+        format!("Cost Basis {}", currency),
+        format!("Gain {}", currency),
+        "Unmatched Quantity".to_string(),
+    ])?;
+
+    for disposal in &report.disposals {
+        writer.write_record([
+            disposal.hash.clone(),
+            disposal.date.clone(),
+            report.method.to_string(),
+            if disposal.fee { "fee" } else { "transfer" }.to_string(),
+            disposal.quantity.to_ether_string(),
+            currency.format_plain(disposal.proceeds),
+            currency.format_plain(disposal.cost_basis),
+            currency.format_plain(disposal.gain()),
+            disposal.unmatched.to_ether_string(),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes native transactions to `path`, with a value and a fee column per currency.
+fn write_transactions_csv(
+    path: &Path,
+    transactions: &[Transaction],
+    currencies: &[Currency],
+) -> Result<(), WalletError> {
+    let mut writer = Writer::from_path(path)?;
+
+    let mut header: Vec<String> = [
+        "Hash",
+        "From",
+        "To",
+        "Gas Used",
+        "Gas Price (gwei)",
+        "Fee",
+        "Quantity",
+        "Direction",
+        "Date",
+        "Internal",
+        "Failed",
+    ]
+    .map(String::from)
+    .to_vec();
+    header.extend(
+        currencies
+            .iter()
+            .map(|currency| format!("Value {}", currency)),
+    );
+    header.extend(
+        currencies
+            .iter()
+            .map(|currency| format!("Fee {}", currency)),
+    );
+    writer.write_record(&header)?;
+
+    for tx in transactions {
+        let mut record = vec![
+            tx.hash.clone(),
+            tx.from.clone(),
+            tx.to.clone(),
+            tx.gas_used.to_string(),
+            tx.gas_price.to_gwei_string(),
+            tx.fee().to_ether_string(),
+            tx.quantity.to_ether_string(),
+            tx.direction.to_string(),
+            tx.date.clone(),
+            tx.internal.to_string(),
+            tx.is_error.to_string(),
+        ];
+        record.extend(fiat_columns(currencies, &tx.fiat_values));
+        record.extend(fiat_columns(currencies, &tx.fee_fiat_values));
+        writer.write_record(&record)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes counterparties to `path`, leaving the contract column empty when unknown.
+fn write_counterparties_csv(
+    path: &Path,
+    counterparties: &[Counterparty],
+) -> Result<(), WalletError> {
+    let mut writer = Writer::from_path(path)?;
+
+    writer.write_record([
+        "Address",
+        "Transactions",
+        "Volume In",
+        "Volume Out",
+        "First Interaction",
+        "Last Interaction",
+        "Contract",
+    ])?;
+
+    for counterparty in counterparties {
+        writer.write_record([
+            counterparty.address.clone(),
+            counterparty.transactions.to_string(),
+            counterparty.volume_in.to_ether_string(),
+            counterparty.volume_out.to_ether_string(),
+            counterparty.first_interaction.to_string(),
+            counterparty.last_interaction.to_string(),
+            counterparty
+                .is_contract
+                .map(|is_contract| is_contract.to_string())
+                .unwrap_or_default(),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes ERC-20 token transfers to `path`.
+fn write_token_transfers_csv(path: &Path, transfers: &[TokenTransfer]) -> Result<(), WalletError> {
+    let mut writer = Writer::from_path(path)?;
+
+    writer.write_record([
+        "Hash",
+        "Date",
+        "Direction",
+        "From",
+        "To",
+        "Token",
+        "Contract",
+        "Amount",
+    ])?;
+
+    for transfer in transfers {
+        writer.write_record([
+            transfer.hash.clone(),
+            transfer.date.clone(),
+            transfer.direction.to_string(),
+            transfer.from.clone(),
+            transfer.to.clone(),
+            transfer.symbol.clone(),
+            transfer.contract.clone(),
+            transfer.formatted_amount(),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes ERC-721 and ERC-1155 transfers to `path`.
+fn write_nft_transfers_csv(path: &Path, transfers: &[NftTransfer]) -> Result<(), WalletError> {
+    let mut writer = Writer::from_path(path)?;
+
+    writer.write_record([
+        "Hash",
+        "Date",
+        "Direction",
+        "From",
+        "To",
+        "Standard",
+        "Collection",
+        "Contract",
+        "Token ID",
+        "Amount",
+    ])?;
+
+    for transfer in transfers {
+        writer.write_record([
+            transfer.hash.clone(),
+            transfer.date.clone(),
+            transfer.direction.to_string(),
+            transfer.from.clone(),
+            transfer.to.clone(),
+            transfer.standard.to_string(),
+            transfer.symbol.clone(),
+            transfer.contract.clone(),
+            transfer.token_id.to_string(),
+            transfer.amount.to_string(),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the NFTs the wallet still holds to `path`.
+fn write_nft_holdings_csv(path: &Path, holdings: &[NftHolding]) -> Result<(), WalletError> {
+    let mut writer = Writer::from_path(path)?;
+
+    writer.write_record([
+        "Standard",
+        "Collection",
+        "Contract",
+        "Token ID",
+        "Amount",
+        "Acquired",
+    ])?;
+
+    for holding in holdings {
+        writer.write_record([
+            holding.standard.to_string(),
+            holding.symbol.clone(),
+            holding.contract.clone(),
+            holding.token_id.to_string(),
+            holding.amount.to_string(),
+            holding.acquired.clone(),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Formats `values` as one column per currency, empty where a currency has no value.
+fn fiat_columns(currencies: &[Currency], values: &[FiatAmount]) -> Vec<String> {
+    currencies
+        .iter()
+        .map(|currency| {
+            values
+                .iter()
+                .find(|value| &value.currency == currency)
+                .map(|value| currency.format_plain(value.amount))
+                .unwrap_or_default()
+        })
+        .collect()
+}
@@ -0,0 +1,439 @@
+use std::collections::HashMap;
+
+use serde_json::{Value, json};
+
+use super::chain::Chain;
+use super::etherscan;
+use super::provider::Provider;
+use super::utils;
+use crate::{
+    address::Address,
+    amount::Wei,
+    date_utils,
+    enums::Direction,
+    errors::WalletError,
+    store::{INTERNAL_TRANSACTIONS_KIND, TRANSACTIONS_KIND, TransactionStore},
+    structs::Transaction,
+};
+
+/// Number of blocks covered by a single `trace_filter` request.
+const TRACE_BLOCK_RANGE: u64 = 10_000;
+
+/// Brings the cached internal transaction history of a wallet up to date.
+///
+/// Etherscan's `txlistinternal` list is used when available. If it fails, the
+/// internal transfers are rebuilt from the node instead: first with
+/// `trace_filter`, then, for nodes without the trace API, by replaying each
+/// cached transaction of the wallet with `debug_traceTransaction`. A wallet
+/// never synced is traced from the first block it is known to be active in.
+///
+/// # Returns
+/// * The number of internal transactions added to the cache.
+pub async fn sync_internal_transactions(
+    provider: &Provider,
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+) -> Result<usize, WalletError> {
+    let start_block = store
+        .last_synced_block(chain, wallet, INTERNAL_TRANSACTIONS_KIND)?
+        .map_or(0, |block| block + 1);
+
+    // Blocks covered by the node fallbacks, so they are not traced again on
+    // the next sync.
+    let mut traced_block = None;
+
+    let transactions =
+        match etherscan::fetch_full_history(chain, INTERNAL_TRANSACTIONS_KIND, wallet, start_block)
+            .await
+        {
+            Ok(rows) => rows
+                .iter()
+                .filter(|row| row["isError"].as_str() != Some("1"))
+                .map(|row| parse_internal_transaction(chain, wallet, row))
+                .collect::<Result<Vec<Transaction>, WalletError>>()?,
+            Err(error) => {
+                eprintln!(
+                    "Etherscan internal transactions unavailable ({}), falling back to node traces",
+                    error
+                );
+                let head = utils::get_block_number(provider).await?;
+                let from_block = match start_block {
+                    0 => store.first_seen_block(chain, wallet)?.unwrap_or(head),
+                    block => block,
+                };
+                match trace_filter(provider, chain, wallet, from_block, head).await {
+                    Ok(transactions) => {
+                        traced_block = Some(head);
+                        transactions
+                    }
+                    Err(WalletError::Rpc { .. }) => {
+                        traced_block = store.last_synced_block(chain, wallet, TRANSACTIONS_KIND)?;
+                        trace_known_transactions(provider, store, chain, wallet, start_block)
+                            .await?
+                    }
+                    Err(error) => return Err(error),
+                }
+            }
+        };
+
+    let last_block = transactions
+        .iter()
+        .map(|tx| tx.block_number)
+        .chain(traced_block)
+        .max()
+        .unwrap_or(start_block.saturating_sub(1));
+    store.save_transactions(
+        chain,
+        wallet,
+        &transactions,
+        INTERNAL_TRANSACTIONS_KIND,
+        last_block,
+    )
+}
+
+/// Parses a single Etherscan `txlistinternal` row of `wallet` into an internal [`Transaction`].
+fn parse_internal_transaction(
+    chain: Chain,
+    wallet: &Address,
+    row: &Value,
+) -> Result<Transaction, WalletError> {
+    let field = |name: &str| {
+        row[name].as_str().ok_or(WalletError::Missing {
+            field: name.to_string(),
+        })
+    };
+
+    let timestamp = field("timeStamp")?.to_string();
+    let from = field("from")?.to_string();
+    let to = field("to")?.to_string();
+
+    Ok(Transaction {
+        chain,
+        hash: field("hash")?.to_string(),
+        block_number: etherscan::block_number(row)?,
+        timestamp: timestamp.parse::<u64>()?,
+        direction: Direction::of(wallet.as_str(), &from, &to),
+        from,
+        to,
+        quantity: Wei::from_dec_str(field("value")?)?,
+        gas_used: 0,
+        gas_price: Wei::ZERO,
+        date: date_utils::epoch_converter(timestamp),
+        internal: true,
+        is_error: false,
+        trace_id: field("traceId")?.to_string(),
+        fiat_values: Vec::new(),
+        fee_fiat_values: Vec::new(),
+    })
+}
+
+/// Collects internal transfers from and to the wallet with the `trace_filter` API.
+///
+/// Supported by Erigon, Nethermind, Reth and most archive node providers.
+/// Blocks `start_block` to `head` are read in chunks of [`TRACE_BLOCK_RANGE`]
+/// blocks. Value moved by calls, contract creations and self-destructs is
+/// collected; transfers inside a call that reverted, or below one, are
+/// skipped, which takes a `trace_transaction` per transaction involved.
+async fn trace_filter(
+    provider: &Provider,
+    chain: Chain,
+    wallet: &Address,
+    start_block: u64,
+    head: u64,
+) -> Result<Vec<Transaction>, WalletError> {
+    let mut traces = Vec::new();
+    let mut from_block = start_block;
+    while from_block <= head {
+        let to_block = (from_block + TRACE_BLOCK_RANGE - 1).min(head);
+        for direction in ["fromAddress", "toAddress"] {
+            let result = provider
+                .request(
+                    "trace_filter",
+                    json!([{
+                        "fromBlock": format!("0x{:x}", from_block),
+                        "toBlock": format!("0x{:x}", to_block),
+                        direction: [wallet.as_str()],
+                    }]),
+                )
+                .await?;
+            traces.extend(result.as_array().cloned().unwrap_or_default());
+        }
+        from_block = to_block + 1;
+    }
+
+    let mut timestamps: HashMap<u64, u64> = HashMap::new();
+    let mut reverted: HashMap<String, Vec<Vec<u64>>> = HashMap::new();
+    let mut transactions = Vec::new();
+
+    for trace in traces {
+        let path = trace_path(&trace);
+        if path.is_empty() || trace.get("error").is_some() {
+            continue;
+        }
+        let Some((from, to, value)) = trace_transfer(&trace) else {
+            continue;
+        };
+        let quantity = Wei::from_hex_str(value)?;
+        if quantity == Wei::ZERO {
+            continue;
+        }
+
+        // Only the failed frame carries an error, not the calls below it.
+        let hash = trace["transactionHash"].as_str().unwrap_or_default();
+        if !reverted.contains_key(hash) {
+            let frames = reverted_frames(provider, hash).await?;
+            reverted.insert(hash.to_string(), frames);
+        }
+        if reverted[hash].iter().any(|frame| path.starts_with(frame)) {
+            continue;
+        }
+
+        let block_number = trace["blockNumber"].as_u64().ok_or(WalletError::Missing {
+            field: "blockNumber".to_string(),
+        })?;
+        let timestamp = match timestamps.get(&block_number) {
+            Some(timestamp) => *timestamp,
+            None => {
+                let timestamp = utils::get_block_timestamp(provider, block_number).await?;
+                timestamps.insert(block_number, timestamp);
+                timestamp
+            }
+        };
+
+        transactions.push(Transaction {
+            chain,
+            hash: hash.to_string(),
+            block_number,
+            timestamp,
+            from: from.to_string(),
+            to: to.to_string(),
+            quantity,
+            direction: Direction::of(wallet.as_str(), from, to),
+            gas_used: 0,
+            gas_price: Wei::ZERO,
+            date: date_utils::epoch_converter(timestamp.to_string()),
+            internal: true,
+            is_error: false,
+            trace_id: trace_id(&path),
+            fiat_values: Vec::new(),
+            fee_fiat_values: Vec::new(),
+        });
+    }
+
+    transactions.sort_by_key(|tx| tx.block_number);
+    Ok(transactions)
+}
+
+/// Returns the sender, recipient and hexadecimal value of a `trace_filter`
+/// trace that can move value, or `None` for other traces.
+///
+/// A delegate call runs in the caller's context, so the value it shows was
+/// already moved by its parent.
+fn trace_transfer(trace: &Value) -> Option<(&str, &str, &str)> {
+    fn field(value: &Value) -> &str {
+        value.as_str().unwrap_or_default()
+    }
+
+    let action = &trace["action"];
+    match trace["type"].as_str()? {
+        "call" if action["callType"] != "delegatecall" => Some((
+            field(&action["from"]),
+            field(&action["to"]),
+            action["value"].as_str().unwrap_or("0x0"),
+        )),
+        "create" => Some((
+            field(&action["from"]),
+            field(&trace["result"]["address"]),
+            action["value"].as_str().unwrap_or("0x0"),
+        )),
+        "suicide" => Some((
+            field(&action["address"]),
+            field(&action["refundAddress"]),
+            action["balance"].as_str().unwrap_or("0x0"),
+        )),
+        _ => None,
+    }
+}
+
+/// Returns the trace addresses of the frames of a transaction that failed,
+/// from `trace_transaction`.
+async fn reverted_frames(provider: &Provider, hash: &str) -> Result<Vec<Vec<u64>>, WalletError> {
+    let traces = provider.request("trace_transaction", json!([hash])).await?;
+    Ok(traces
+        .as_array()
+        .map(|traces| {
+            traces
+                .iter()
+                .filter(|trace| trace.get("error").is_some())
+                .map(trace_path)
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Returns the `traceAddress` of a trace, the position of its frame in the call tree.
+fn trace_path(trace: &Value) -> Vec<u64> {
+    trace["traceAddress"]
+        .as_array()
+        .map(|indexes| {
+            indexes
+                .iter()
+                .map(|index| index.as_u64().unwrap_or_default())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Collects internal transfers by replaying the wallet's cached transactions
+/// with `debug_traceTransaction` and its built-in `callTracer`.
+///
+/// Only finds internal transfers inside transactions the wallet sent or
+/// received directly, which is the best a node without `trace_filter` offers.
+/// Failed transactions are skipped, since everything they did was reverted,
+/// and so are failed calls inside a transaction with the calls below them.
+async fn trace_known_transactions(
+    provider: &Provider,
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+    start_block: u64,
+) -> Result<Vec<Transaction>, WalletError> {
+    let known = store.load_transactions(chain, wallet, None)?;
+    let mut transactions = Vec::new();
+
+    for parent in known
+        .iter()
+        .filter(|tx| !tx.internal && !tx.is_error && tx.block_number >= start_block)
+    {
+        let call = provider
+            .request(
+                "debug_traceTransaction",
+                json!([parent.hash, { "tracer": "callTracer" }]),
+            )
+            .await?;
+
+        let mut calls = Vec::new();
+        collect_calls(&call, &mut Vec::new(), &mut calls);
+
+        for (path, call) in calls {
+            let from = call["from"].as_str().unwrap_or_default();
+            let to = call["to"].as_str().unwrap_or_default();
+            let involved = from.eq_ignore_ascii_case(wallet.as_str())
+                || to.eq_ignore_ascii_case(wallet.as_str());
+            let quantity = Wei::from_hex_str(call["value"].as_str().unwrap_or("0x0"))?;
+
+            if !involved || quantity == Wei::ZERO {
+                continue;
+            }
+
+            transactions.push(Transaction {
+                chain,
+                hash: parent.hash.clone(),
+                block_number: parent.block_number,
+                timestamp: parent.timestamp,
+                from: from.to_string(),
+                to: to.to_string(),
+                quantity,
+                direction: Direction::of(wallet.as_str(), from, to),
+                gas_used: 0,
+                gas_price: Wei::ZERO,
+                date: parent.date.clone(),
+                internal: true,
+                is_error: false,
+                trace_id: trace_id(&path),
+                fiat_values: Vec::new(),
+                fee_fiat_values: Vec::new(),
+            });
+        }
+    }
+
+    Ok(transactions)
+}
+
+/// Flattens the nested `calls` of a `callTracer` frame, skipping the top-level call.
+///
+/// A failed frame is skipped with every call below it, since they were all
+/// reverted. Delegate calls are left out, but not the calls they make: they
+/// run in the caller's context and only repeat the value of their parent.
+fn collect_calls<'a>(frame: &'a Value, path: &mut Vec<u64>, out: &mut Vec<(Vec<u64>, &'a Value)>) {
+    if let Some(children) = frame["calls"].as_array() {
+        for (index, child) in children.iter().enumerate() {
+            if child.get("error").is_some() {
+                continue;
+            }
+            path.push(index as u64);
+            if child["type"] != "DELEGATECALL" {
+                out.push((path.clone(), child));
+            }
+            collect_calls(child, path, out);
+            path.pop();
+        }
+    }
+}
+
+/// Formats a position in the call tree the way Etherscan formats `traceId`, e.g. `0_1`.
+fn trace_id(path: &[u64]) -> String {
+    path.iter()
+        .map(|index| index.to_string())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_transfers_cover_calls_creations_and_self_destructs() {
+        let call = json!({
+            "type": "call",
+            "action": { "callType": "call", "from": "0xaa", "to": "0xbb", "value": "0x10" },
+        });
+        let create = json!({
+            "type": "create",
+            "action": { "from": "0xaa", "value": "0x20" },
+            "result": { "address": "0xcc" },
+        });
+        let suicide = json!({
+            "type": "suicide",
+            "action": { "address": "0xcc", "refundAddress": "0xaa", "balance": "0x30" },
+        });
+        let delegate = json!({
+            "type": "call",
+            "action": { "callType": "delegatecall", "from": "0xaa", "to": "0xbb", "value": "0x10" },
+        });
+
+        assert_eq!(trace_transfer(&call), Some(("0xaa", "0xbb", "0x10")));
+        assert_eq!(trace_transfer(&create), Some(("0xaa", "0xcc", "0x20")));
+        assert_eq!(trace_transfer(&suicide), Some(("0xcc", "0xaa", "0x30")));
+        assert_eq!(trace_transfer(&delegate), None);
+        assert_eq!(trace_transfer(&json!({ "type": "reward" })), None);
+    }
+
+    #[test]
+    fn reverted_call_trees_are_skipped() {
+        let frame = json!({
+            "type": "CALL",
+            "calls": [
+                {
+                    "type": "CALL",
+                    "error": "execution reverted",
+                    "calls": [{ "type": "CALL", "value": "0x1" }],
+                },
+                {
+                    "type": "DELEGATECALL",
+                    "calls": [{ "type": "SELFDESTRUCT", "value": "0x2" }],
+                },
+                { "type": "CREATE", "value": "0x3" },
+            ],
+        });
+
+        let mut calls = Vec::new();
+        collect_calls(&frame, &mut Vec::new(), &mut calls);
+
+        let ids: Vec<String> = calls.iter().map(|(path, _)| trace_id(path)).collect();
+        assert_eq!(ids, ["1_0", "2"]);
+        assert_eq!(calls[0].1["type"], "SELFDESTRUCT");
+    }
+}
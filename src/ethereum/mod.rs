@@ -1,4 +1,14 @@
+pub mod abi;
+pub mod chain;
+pub mod dashboard;
 pub mod display;
+pub mod ens;
+pub mod erc20;
 pub mod ether_account;
+pub mod etherscan;
 pub mod export;
+pub mod internal;
+pub mod nft;
+pub mod provider;
 pub mod utils;
+pub mod watch;
@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use primitive_types::U256;
+use serde_json::{Value, json};
+
+use super::abi::{self, eth_call};
+use super::chain::Chain;
+use super::erc20::SYMBOL_SELECTOR;
+use super::etherscan;
+use super::provider::Provider;
+use super::utils;
+use crate::{
+    address::Address,
+    date_utils,
+    enums::{Direction, NftStandard},
+    errors::WalletError,
+    store::{ERC721_TRANSFERS_KIND, ERC1155_TRANSFERS_KIND, TransactionStore},
+    structs::{NftHolding, NftTransfer},
+};
+
+/// Topic of `Transfer(address,address,uint256)`, shared with ERC-20.
+const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+/// Topic of `TransferSingle(address,address,address,uint256,uint256)`.
+const TRANSFER_SINGLE_TOPIC: &str =
+    "0xc3d58168c5ae7397731d063d5bbf3d657854427343f4c083240f7aacaa2d0f62";
+
+/// Topic of `TransferBatch(address,address,address,uint256[],uint256[])`.
+const TRANSFER_BATCH_TOPIC: &str =
+    "0x4a39dc06d4c0dbc64b70af90fd698a233a518aa5d07e595d983b8c0526c8f7fb";
+
+/// Number of blocks covered by a single `eth_getLogs` request.
+const LOG_BLOCK_RANGE: u64 = 10_000;
+
+/// Returns the ERC-721 and ERC-1155 transfers of a wallet, oldest first.
+///
+/// The local cache is synced first, then read.
+///
+/// # Arguments
+/// * `provider` - JSON-RPC provider, used when Etherscan is unavailable.
+/// * `store` - Local transaction cache.
+/// * `chain` - Chain to query.
+/// * `wallet` - Wallet address.
+pub async fn get_nft_transfers(
+    provider: &Provider,
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+) -> Result<Vec<NftTransfer>, WalletError> {
+    sync_nft_transfers(provider, store, chain, wallet).await?;
+    store.load_nft_transfers(chain, wallet)
+}
+
+/// Brings the cached NFT transfer history of a wallet up to date.
+///
+/// Etherscan's `tokennfttx` and `token1155tx` lists are used when available.
+/// A standard whose list fails is synced from `Transfer`, or `TransferSingle`
+/// and `TransferBatch`, logs read from the node instead, while the other
+/// standard keeps what Etherscan returned.
+///
+/// # Returns
+/// * The number of NFT transfers added to the cache.
+pub async fn sync_nft_transfers(
+    provider: &Provider,
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+) -> Result<usize, WalletError> {
+    let mut inserted = 0;
+    for (standard, kind) in [
+        (NftStandard::Erc721, ERC721_TRANSFERS_KIND),
+        (NftStandard::Erc1155, ERC1155_TRANSFERS_KIND),
+    ] {
+        let cursor = store.last_synced_block(chain, wallet, kind)?;
+        let start_block = cursor.map_or(0, |block| block + 1);
+
+        match etherscan::fetch_full_history(chain, kind, wallet, start_block).await {
+            Ok(rows) => {
+                let transfers = rows
+                    .iter()
+                    .map(|row| parse_nft_transfer(chain, wallet, standard, row))
+                    .collect::<Result<Vec<NftTransfer>, WalletError>>()?;
+                let last_block = transfers
+                    .last()
+                    .map_or(start_block.saturating_sub(1), |transfer| {
+                        transfer.block_number
+                    });
+                inserted +=
+                    store.save_nft_transfers(chain, wallet, &transfers, kind, last_block)?;
+            }
+            Err(error) => {
+                eprintln!(
+                    "Etherscan {} transfers unavailable ({}), falling back to node logs",
+                    standard, error
+                );
+                inserted +=
+                    sync_from_logs(provider, store, chain, wallet, standard, kind, cursor).await?;
+            }
+        }
+    }
+    Ok(inserted)
+}
+
+/// Parses a single Etherscan `tokennfttx` or `token1155tx` row into an [`NftTransfer`].
+fn parse_nft_transfer(
+    chain: Chain,
+    wallet: &Address,
+    standard: NftStandard,
+    row: &Value,
+) -> Result<NftTransfer, WalletError> {
+    let field = |name: &str| {
+        row[name].as_str().ok_or(WalletError::Missing {
+            field: name.to_string(),
+        })
+    };
+    let parse_uint = |value: &str| {
+        U256::from_dec_str(value).map_err(|_| WalletError::AmountParse {
+            value: value.to_string(),
+        })
+    };
+
+    let from = field("from")?.to_string();
+    let to = field("to")?.to_string();
+    let timestamp = field("timeStamp")?.to_string();
+    let amount = match standard {
+        NftStandard::Erc721 => U256::one(),
+        NftStandard::Erc1155 => parse_uint(field("tokenValue")?)?,
+    };
+
+    Ok(NftTransfer {
+        chain,
+        hash: field("hash")?.to_string(),
+        block_number: etherscan::block_number(row)?,
+        // Not every explorer returns the log index; transfers then only
+        // differ by token and parties within a transaction.
+        log_index: row["logIndex"]
+            .as_str()
+            .map(str::parse::<u64>)
+            .transpose()?
+            .unwrap_or_default(),
+        timestamp: timestamp.parse::<u64>()?,
+        contract: field("contractAddress")?.to_lowercase(),
+        symbol: field("tokenSymbol")?.to_string(),
+        standard,
+        token_id: parse_uint(field("tokenID")?)?,
+        amount,
+        direction: Direction::of(wallet.as_str(), &from, &to),
+        from,
+        to,
+        date: date_utils::epoch_converter(timestamp),
+    })
+}
+
+/// Syncs the transfers of one NFT standard of a wallet from the node's event logs.
+///
+/// Logs are read in chunks of [`LOG_BLOCK_RANGE`] blocks, starting after the
+/// sync cursor of `kind`. A wallet never synced starts from the first block
+/// it is known to be active in, or from the head when nothing of it is
+/// cached, since scanning from genesis would take millions of requests.
+///
+/// # Arguments
+/// * `standard` - Standard whose transfer events are read.
+/// * `kind` - Sync cursor of that standard.
+/// * `cursor` - Last block synced for `kind`, if any.
+async fn sync_from_logs(
+    provider: &Provider,
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+    standard: NftStandard,
+    kind: &str,
+    cursor: Option<u64>,
+) -> Result<usize, WalletError> {
+    let head = utils::get_block_number(provider).await?;
+    let start_block = match cursor {
+        Some(block) => block + 1,
+        None => store.first_seen_block(chain, wallet)?.unwrap_or(head),
+    };
+
+    let wallet_topic = format!("0x{}", abi::encode_address(wallet.as_str()));
+    let mut symbols: HashMap<String, String> = HashMap::new();
+    let mut timestamps: HashMap<u64, u64> = HashMap::new();
+    let mut transfers = Vec::new();
+
+    let mut from_block = start_block;
+    while from_block <= head {
+        let to_block = (from_block + LOG_BLOCK_RANGE - 1).min(head);
+        let topic_filters = match standard {
+            NftStandard::Erc721 => [
+                json!([TRANSFER_TOPIC, wallet_topic]),
+                json!([TRANSFER_TOPIC, null, wallet_topic]),
+            ],
+            NftStandard::Erc1155 => [
+                json!([
+                    [TRANSFER_SINGLE_TOPIC, TRANSFER_BATCH_TOPIC],
+                    null,
+                    wallet_topic
+                ]),
+                json!([
+                    [TRANSFER_SINGLE_TOPIC, TRANSFER_BATCH_TOPIC],
+                    null,
+                    null,
+                    wallet_topic
+                ]),
+            ],
+        };
+
+        for topics in topic_filters {
+            let logs = provider
+                .request(
+                    "eth_getLogs",
+                    json!([{
+                        "fromBlock": format!("0x{:x}", from_block),
+                        "toBlock": format!("0x{:x}", to_block),
+                        "topics": topics,
+                    }]),
+                )
+                .await?;
+
+            for log in logs.as_array().cloned().unwrap_or_default() {
+                let log_topics = log["topics"].as_array().cloned().unwrap_or_default();
+                // ERC-20 transfers share the `Transfer` topic but do not index the value.
+                if log_topics.len() != 4 {
+                    continue;
+                }
+
+                let block_number = hex_u64(&log, "blockNumber")?;
+                let log_index = hex_u64(&log, "logIndex")?;
+                let timestamp = match timestamps.get(&block_number) {
+                    Some(timestamp) => *timestamp,
+                    None => {
+                        let timestamp = utils::get_block_timestamp(provider, block_number).await?;
+                        timestamps.insert(block_number, timestamp);
+                        timestamp
+                    }
+                };
+
+                let contract = log["address"].as_str().unwrap_or_default().to_lowercase();
+                let symbol = match symbols.get(&contract) {
+                    Some(symbol) => symbol.clone(),
+                    None => {
+                        let symbol = match eth_call(provider, &contract, SYMBOL_SELECTOR).await {
+                            Ok(data) => abi::decode_string(&data).unwrap_or_default(),
+                            Err(_) => String::new(),
+                        };
+                        symbols.insert(contract.clone(), symbol.clone());
+                        symbol
+                    }
+                };
+
+                let transfer = |standard, from: String, to: String, token_id, amount| NftTransfer {
+                    chain,
+                    hash: log["transactionHash"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    block_number,
+                    log_index,
+                    timestamp,
+                    contract: contract.clone(),
+                    symbol: symbol.clone(),
+                    standard,
+                    token_id,
+                    amount,
+                    direction: Direction::of(wallet.as_str(), &from, &to),
+                    from,
+                    to,
+                    date: date_utils::epoch_converter(timestamp.to_string()),
+                };
+
+                let topic = |index: usize| log_topics[index].as_str().unwrap_or_default();
+                let data = log["data"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .trim_start_matches("0x");
+
+                if topic(0) == TRANSFER_TOPIC {
+                    let token_id = abi::decode_uint(topic(3).trim_start_matches("0x"))?;
+                    transfers.push(transfer(
+                        NftStandard::Erc721,
+                        topic_address(topic(1)),
+                        topic_address(topic(2)),
+                        token_id,
+                        U256::one(),
+                    ));
+                } else if topic(0) == TRANSFER_SINGLE_TOPIC {
+                    transfers.push(transfer(
+                        NftStandard::Erc1155,
+                        topic_address(topic(2)),
+                        topic_address(topic(3)),
+                        abi::decode_uint_at(data, 0)?,
+                        abi::decode_uint_at(data, 1)?,
+                    ));
+                } else {
+                    let ids = abi::decode_uint_array(data, 0)?;
+                    let amounts = abi::decode_uint_array(data, 1)?;
+                    for (token_id, amount) in ids.into_iter().zip(amounts) {
+                        transfers.push(transfer(
+                            NftStandard::Erc1155,
+                            topic_address(topic(2)),
+                            topic_address(topic(3)),
+                            token_id,
+                            amount,
+                        ));
+                    }
+                }
+            }
+        }
+
+        from_block = to_block + 1;
+    }
+
+    transfers.sort_by_key(|transfer| (transfer.block_number, transfer.log_index));
+
+    let head = head.max(start_block.saturating_sub(1));
+    store.save_nft_transfers(chain, wallet, &transfers, kind, head)
+}
+
+/// Replays NFT transfers to find the tokens a wallet still holds.
+///
+/// # Arguments
+/// * `transfers` - Transfers of the wallet, oldest first.
+///
+/// # Returns
+/// * The held tokens in the order they were first received.
+pub fn nft_holdings(transfers: &[NftTransfer]) -> Vec<NftHolding> {
+    let mut holdings: Vec<NftHolding> = Vec::new();
+    let mut index: HashMap<(String, U256), usize> = HashMap::new();
+
+    for transfer in transfers {
+        let key = (transfer.contract.clone(), transfer.token_id);
+        let position = *index.entry(key).or_insert_with(|| {
+            holdings.push(NftHolding {
+                contract: transfer.contract.clone(),
+                symbol: transfer.symbol.clone(),
+                standard: transfer.standard,
+                token_id: transfer.token_id,
+                amount: U256::zero(),
+                acquired: String::new(),
+            });
+            holdings.len() - 1
+        });
+        let holding = &mut holdings[position];
+
+        match transfer.direction {
+            Direction::In => {
+                holding.amount = holding.amount.saturating_add(transfer.amount);
+                holding.acquired = transfer.date.clone();
+            }
+            Direction::Out => {
+                holding.amount = holding.amount.saturating_sub(transfer.amount);
+            }
+            Direction::SelfTransfer | Direction::ContractCreation | Direction::Unrelated => {}
+        }
+    }
+
+    holdings.retain(|holding| !holding.amount.is_zero());
+    holdings
+}
+
+/// Extracts the address stored in an indexed event topic.
+fn topic_address(topic: &str) -> String {
+    format!("0x{}", &topic[topic.len().saturating_sub(40)..])
+}
+
+/// Parses the `0x`-prefixed quantity `field` of a JSON-RPC log.
+fn hex_u64(log: &Value, field: &str) -> Result<u64, WalletError> {
+    let hex_value = log[field].as_str().ok_or(WalletError::Missing {
+        field: field.to_string(),
+    })?;
+    Ok(u64::from_str_radix(hex_value.trim_start_matches("0x"), 16)?)
+}
@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use reqwest::Client;
+use serde_json::{Value, json};
+
+use crate::errors::WalletError;
+
+/// JSON-RPC client for an Ethereum node.
+///
+/// Every node call made by the tracker goes through a `Provider`, so the
+/// endpoint can point at a public provider, a private node or a local devnet.
+pub struct Provider {
+    client: Client,
+    url: String,
+    next_id: AtomicU64,
+}
+
+impl Provider {
+    /// Creates a provider for the given JSON-RPC endpoint.
+    pub fn new(url: impl Into<String>) -> Self {
+        Provider {
+            client: Client::new(),
+            url: url.into(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Sends a JSON-RPC request and returns its `result` field.
+    ///
+    /// # Arguments
+    /// * `method` - JSON-RPC method name, e.g. `eth_getBalance`.
+    /// * `params` - Positional parameters as a JSON array.
+    ///
+    /// # Errors
+    /// Returns [`WalletError::Rpc`] if the node answers with an error object.
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, WalletError> {
+        let body = json!({
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params
+        });
+
+        let mut response = self
+            .client
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            return Err(WalletError::Rpc {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+
+        match response.get_mut("result") {
+            Some(result) => Ok(result.take()),
+            None => Err(WalletError::Missing {
+                field: "result".to_string(),
+            }),
+        }
+    }
+}
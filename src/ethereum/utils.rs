@@ -1,225 +1,1100 @@
-use dotenv::dotenv;
-use reqwest::Client;
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, Utc};
 use serde_json::{Value, json};
 
+use super::chain::Chain;
+use super::erc20;
+use super::etherscan;
+use super::internal;
+use super::provider::Provider;
 use crate::{
+    address::Address,
+    amount::Wei,
+    balance_history::{self, BalanceTimeline, SamplingInterval},
+    cost_basis::{self, CostBasisMethod, CostBasisReport},
+    currency::{Currency, FiatAmount},
     date_utils,
+    enums::{CounterpartySort, Direction},
     errors::WalletError,
-    structs::{Statistics, Transaction},
+    prices::PriceOracle,
+    store::{TRANSACTIONS_KIND, TransactionStore},
+    structs::{
+        Counterparty, FeeSummary, NotableTransfer, Statistics, TokenBalance, TokenTransfer,
+        Transaction,
+    },
 };
 
-/// Number of Wei in one Ether.
-const WEI_VALUE: i64 = 1_000_000_000_000_000_000;
-
 /// Returns the ETH balance of the given wallet address.
 ///
 /// Uses a JSON-RPC call to an Ethereum node.
 ///
 /// # Arguments
-/// * `wallet` - A string slice containing the wallet address.
+/// * `provider` - JSON-RPC provider to query.
+/// * `wallet` - Wallet address.
 ///
 /// # Returns
-/// * `Ok(f64)` - The balance in ETH.
+/// * `Ok(Wei)` - The exact balance in wei.
 /// * `Err` - If the API call fails or parsing fails.
-pub async fn get_balance(wallet: &str) -> Result<u128, WalletError> {
-    let client = Client::new();
-
-    let body = json!({
-        "id": 1,
-        "jsonrpc": "2.0",
-        "method": "eth_getBalance",
-        "params": [wallet, "pending"]
-    });
+pub async fn get_balance(provider: &Provider, wallet: &Address) -> Result<Wei, WalletError> {
+    let response = provider
+        .request("eth_getBalance", json!([wallet.as_str(), "pending"]))
+        .await?;
+
+    let hex_balance = response.as_str().ok_or(WalletError::Missing {
+        field: "result".to_string(),
+    })?;
+
+    Wei::from_hex_str(hex_balance)
+}
 
-    let response = client
-        .post("https://nd-422-757-666.p2pify.com/0a9d79d93fb2f4a4b1e04695da2b77a7")
-        .json(&body)
-        .send()
-        .await?
-        .json::<Value>()
+/// Returns whether an address holds contract code.
+///
+/// # Arguments
+/// * `provider` - JSON-RPC provider to query.
+/// * `address` - Address to check.
+pub async fn is_contract(provider: &Provider, address: &str) -> Result<bool, WalletError> {
+    let response = provider
+        .request("eth_getCode", json!([address, "latest"]))
         .await?;
 
-    let hex_balance = response["result"].as_str().ok_or(WalletError::Missing {
+    let code = response.as_str().ok_or(WalletError::Missing {
+        field: "result".to_string(),
+    })?;
+    Ok(!code.trim_start_matches("0x").is_empty())
+}
+
+/// Returns the number of the latest block known to the node.
+pub async fn get_block_number(provider: &Provider) -> Result<u64, WalletError> {
+    let response = provider.request("eth_blockNumber", json!([])).await?;
+
+    let block_number = response.as_str().ok_or(WalletError::Missing {
         field: "result".to_string(),
     })?;
+    Ok(u64::from_str_radix(
+        block_number.trim_start_matches("0x"),
+        16,
+    )?)
+}
+
+/// Returns the Unix timestamp of a block.
+pub async fn get_block_timestamp(
+    provider: &Provider,
+    block_number: u64,
+) -> Result<u64, WalletError> {
+    let block = provider
+        .request(
+            "eth_getBlockByNumber",
+            json!([format!("0x{:x}", block_number), false]),
+        )
+        .await?;
+
+    let timestamp = block["timestamp"].as_str().ok_or(WalletError::Missing {
+        field: "timestamp".to_string(),
+    })?;
+    Ok(u64::from_str_radix(timestamp.trim_start_matches("0x"), 16)?)
+}
+
+/// Returns the fiat balance of a given wallet address in each quote currency.
+///
+/// Fetches the native balance and converts it at the spot prices given by
+/// the configured price sources.
+///
+/// # Arguments
+/// * `provider` - JSON-RPC provider used for the balance lookup.
+/// * `prices` - Price sources used for the conversion.
+/// * `chain` - Chain the provider belongs to, used to pick the priced asset.
+/// * `wallet` - Wallet address.
+/// * `currencies` - Quote currencies, in display order.
+pub async fn get_fiat_balance(
+    provider: &Provider,
+    prices: &PriceOracle,
+    chain: Chain,
+    wallet: &Address,
+    currencies: &[Currency],
+) -> Result<Vec<FiatAmount>, WalletError> {
+    let eth_balance: Wei = get_balance(provider, wallet).await?;
 
-    let wei_balance = u128::from_str_radix(&hex_balance[2..], 16)?;
-    Ok(wei_balance as u128 / WEI_VALUE as u128)
+    let mut balances = Vec::new();
+    for currency in currencies {
+        let rate = prices
+            .spot_price(chain.native_symbol(), currency.code())
+            .await?;
+        balances.push(FiatAmount {
+            currency: currency.clone(),
+            amount: rate * eth_balance.to_ether_f64(),
+        });
+    }
+    Ok(balances)
+}
+
+/// Returns the ERC-20 balances of a wallet.
+///
+/// Queries the configured token contracts plus every token the wallet ever
+/// received or sent according to its cached token transfer history. Tokens
+/// with a zero balance are left out, as are contracts that do not answer the
+/// ERC-20 calls.
+///
+/// # Arguments
+/// * `provider` - JSON-RPC provider of the chain.
+/// * `store` - Local transaction cache.
+/// * `chain` - Chain to query.
+/// * `wallet` - Wallet address.
+/// * `tokens` - Configured token contracts.
+pub async fn get_token_balances(
+    provider: &Provider,
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+    tokens: &[Address],
+) -> Result<Vec<TokenBalance>, WalletError> {
+    let mut contracts: Vec<Address> = tokens.to_vec();
+
+    match erc20::get_token_transfers(store, chain, wallet, None).await {
+        Ok(transfers) => {
+            for transfer in transfers {
+                contracts.push(Address::parse(&transfer.contract)?);
+            }
+        }
+        Err(error) => eprintln!("Could not discover tokens from transfer history: {}", error),
+    }
+
+    contracts.sort();
+    contracts.dedup();
+
+    let mut balances = Vec::new();
+    for contract in contracts {
+        match erc20::get_token_balance(provider, &contract, wallet).await {
+            Ok(balance) if !balance.balance.is_zero() => balances.push(balance),
+            Ok(_) => {}
+            Err(error) => eprintln!("Skipping token {}: {}", contract, error),
+        }
+    }
+
+    Ok(balances)
+}
+
+/// Values each transaction in the quote currencies at the price of its UTC day.
+///
+/// Daily prices are read from the local cache first and only asked from the
+/// price sources when missing. Only settled prices are cached, so the current
+/// day and prices from a source without history are asked again on the next
+/// run. Zero-value transactions are valued at zero
+/// without a lookup. A transaction whose price cannot be found is left
+/// without a value in that currency.
+///
+/// # Arguments
+/// * `store` - Local cache, also holding past daily prices.
+/// * `prices` - Price sources with price history.
+/// * `chain` - Chain of the transactions, used to pick the priced asset.
+/// * `transactions` - Transactions to value in place.
+/// * `currencies` - Quote currencies, in display order.
+pub async fn value_transactions(
+    store: &TransactionStore,
+    prices: &PriceOracle,
+    chain: Chain,
+    transactions: &mut [Transaction],
+    currencies: &[Currency],
+) -> Result<(), WalletError> {
+    let asset = chain.native_symbol();
+    let mut missing: HashSet<(String, NaiveDate)> = HashSet::new();
+    // Prices that are not cached in the store, kept for the other transactions of the day.
+    let mut unsettled: HashMap<(String, NaiveDate), f64> = HashMap::new();
+
+    for tx in transactions.iter_mut() {
+        let date = utc_date(tx.timestamp);
+
+        tx.fiat_values.clear();
+        tx.fee_fiat_values.clear();
+        for currency in currencies {
+            let price = if tx.quantity == Wei::ZERO && tx.fee() == Wei::ZERO {
+                Some(0.0)
+            } else if let Some(price) = store.load_price(asset, currency.code(), date)? {
+                Some(price)
+            } else if let Some(price) = unsettled.get(&(currency.code().to_string(), date)) {
+                Some(*price)
+            } else if missing.contains(&(currency.code().to_string(), date)) {
+                None
+            } else {
+                match prices.historical_price(asset, currency.code(), date).await {
+                    Ok(daily) => {
+                        if daily.settled {
+                            store.save_price(asset, currency.code(), date, daily.price)?;
+                        } else {
+                            unsettled.insert((currency.code().to_string(), date), daily.price);
+                        }
+                        Some(daily.price)
+                    }
+                    Err(_) => {
+                        missing.insert((currency.code().to_string(), date));
+                        None
+                    }
+                }
+            };
+
+            if let Some(price) = price {
+                tx.fiat_values.push(FiatAmount {
+                    currency: currency.clone(),
+                    amount: price * tx.quantity.to_ether_f64(),
+                });
+                tx.fee_fiat_values.push(FiatAmount {
+                    currency: currency.clone(),
+                    amount: price * tx.fee().to_ether_f64(),
+                });
+            }
+        }
+    }
+
+    if !missing.is_empty() {
+        eprintln!(
+            "No historical {} price found for {} day(s); those transactions are shown without fiat value",
+            asset,
+            missing.len()
+        );
+    }
+    Ok(())
 }
 
-/// Returns the fiat balance (in USD) of a given wallet address.
+/// Computes the realized and unrealized gains of the wallet's native currency.
 ///
-/// Fetches ETH balance and converts it to USD using Coinbase exchange rates.
+/// The full history is synced and valued in `currency` first; unrealized
+/// gains use the current balance and spot price.
 ///
 /// # Arguments
-/// * `wallet` - A string slice containing the wallet address.
-pub async fn get_fiat_balance(wallet: &str) -> Result<f64, WalletError> {
-    println!("starting");
-    let eth_balance: u128 = get_balance(wallet).await?;
-    println!("here");
-    let client = Client::new();
-
-    let response = client
-        .get("https://api.coinbase.com/v2/exchange-rates?currency=ETH")
-        .send()
-        .await?
-        .json::<Value>()
+/// * `provider` - JSON-RPC provider of the chain.
+/// * `store` - Local transaction cache.
+/// * `prices` - Price sources for historical and spot prices.
+/// * `chain` - Chain to query.
+/// * `wallet` - Wallet address.
+/// * `currency` - Currency the gains are expressed in.
+/// * `method` - Lot matching method.
+pub async fn get_cost_basis(
+    provider: &Provider,
+    store: &TransactionStore,
+    prices: &PriceOracle,
+    chain: Chain,
+    wallet: &Address,
+    currency: &Currency,
+    method: CostBasisMethod,
+) -> Result<CostBasisReport, WalletError> {
+    let mut transactions = get_all_transactions(provider, store, chain, wallet).await?;
+    value_transactions(
+        store,
+        prices,
+        chain,
+        &mut transactions,
+        std::slice::from_ref(currency),
+    )
+    .await?;
+
+    let balance = get_balance(provider, wallet).await?;
+    let spot_price = prices
+        .spot_price(chain.native_symbol(), currency.code())
         .await?;
 
-    let rate = response["data"]["rates"]["USD"]
-        .as_str()
-        .ok_or(WalletError::Missing {
-            field: "data.rates.USD".to_string(),
-        })?;
-    println!("The rate is {}", rate);
-    Ok(rate.trim_end().parse::<f64>()? * eth_balance as f64)
+    Ok(cost_basis::compute_cost_basis(
+        method,
+        &transactions,
+        currency,
+        balance,
+        spot_price,
+    ))
 }
 
-/// Returns a list of transactions for the given wallet.
+/// Returns the most recent transactions of the given wallet, oldest first.
 ///
-/// Uses the Etherscan API.
+/// The local cache is synced first, then read. Internal transactions are
+/// merged into the list.
 ///
 /// # Arguments
-/// * `wallet` - Wallet address as a string slice.
-/// * `transactions_offset` - Max number of transactions to fetch.
+/// * `provider` - JSON-RPC provider, used when internal transactions need node traces.
+/// * `store` - Local transaction cache.
+/// * `chain` - Chain to query.
+/// * `wallet` - Wallet address.
+/// * `transactions_offset` - Number of most recent transactions to return.
 pub async fn get_transactions(
-    wallet: &str,
+    provider: &Provider,
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
     transactions_offset: i32,
 ) -> Result<Vec<Transaction>, WalletError> {
-    dotenv().ok();
-    let client = Client::new();
-
-    let etherscan_token = std::env::var("ETHERSCAN_KEY").expect("Etherscan token must be set.");
-
-    let url = format!(
-        concat!(
-            "https://api.etherscan.io/v2/api?",
-            "chainid=1",
-            "&module=account",
-            "&action=txlist",
-            "&address={}",
-            "&startblock=0",
-            "&endblock=99999999",
-            "&page=1",
-            "&offset={}",
-            "&sort=asc",
-            "&apikey={}"
-        ),
-        wallet, transactions_offset, etherscan_token
-    );
-
-    let response = client.get(url).send().await?.json::<Value>().await?;
-    let transactions = response["result"].as_array().ok_or(WalletError::Missing {
-        field: "result".to_string(),
-    })?;
+    sync_transactions(store, chain, wallet).await?;
+    internal::sync_internal_transactions(provider, store, chain, wallet).await?;
+    store.load_transactions(chain, wallet, Some(transactions_offset as usize))
+}
 
-    let mut parsed_transactions = Vec::new();
+/// Returns the complete transaction history of the given wallet, oldest first.
+///
+/// The local cache is synced first, then read. Internal transactions are
+/// merged into the history.
+///
+/// # Arguments
+/// * `provider` - JSON-RPC provider, used when internal transactions need node traces.
+/// * `store` - Local transaction cache.
+/// * `chain` - Chain to query.
+/// * `wallet` - Wallet address.
+pub async fn get_all_transactions(
+    provider: &Provider,
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+) -> Result<Vec<Transaction>, WalletError> {
+    sync_transactions(store, chain, wallet).await?;
+    internal::sync_internal_transactions(provider, store, chain, wallet).await?;
+    store.load_transactions(chain, wallet, None)
+}
 
-    for tx in transactions {
-        let parsed_tx = Transaction {
-            from: tx["from"]
-                .as_str()
-                .ok_or(WalletError::Missing {
-                    field: "from".to_string(),
-                })?
-                .to_string(),
-            to: tx["to"]
-                .as_str()
-                .ok_or(WalletError::Missing {
-                    field: "to".to_string(),
-                })?
-                .to_string(),
-            gas: tx["gasUsed"]
-                .as_str()
-                .ok_or(WalletError::Missing {
-                    field: "gasUsed".to_string(),
-                })?
-                .to_string(),
-            quantity: tx["value"]
-                .as_str()
-                .ok_or(WalletError::Missing {
-                    field: "value".to_string(),
-                })?
-                .parse::<f64>()?
-                / WEI_VALUE as f64,
-            date: date_utils::epoch_converter(
-                tx["timeStamp"]
-                    .as_str()
-                    .ok_or(WalletError::Missing {
-                        field: "timeStamp".to_string(),
-                    })?
-                    .to_string(),
-            ),
-        };
+/// Brings the cached history of a wallet up to date.
+///
+/// Only blocks after the last synced block are requested from Etherscan. The
+/// history is walked through every page and block range, so it is not limited
+/// by Etherscan's 10,000-row window.
+///
+/// # Returns
+/// * The number of transactions added to the cache.
+pub async fn sync_transactions(
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+) -> Result<usize, WalletError> {
+    let start_block = store
+        .last_synced_block(chain, wallet, TRANSACTIONS_KIND)?
+        .map_or(0, |block| block + 1);
 
-        parsed_transactions.push(parsed_tx);
-    }
+    let rows = etherscan::fetch_full_history(chain, TRANSACTIONS_KIND, wallet, start_block).await?;
+    let transactions = rows
+        .iter()
+        .map(|row| parse_transaction(chain, wallet, row))
+        .collect::<Result<Vec<Transaction>, WalletError>>()?;
 
-    Ok(parsed_transactions)
+    let last_block = transactions
+        .last()
+        .map_or(start_block.saturating_sub(1), |tx| tx.block_number);
+    store.save_transactions(chain, wallet, &transactions, TRANSACTIONS_KIND, last_block)
 }
 
-/// Returns the average gas used across transactions of a wallet.
+/// Parses a single Etherscan `txlist` row of `wallet` into a [`Transaction`].
+fn parse_transaction(
+    chain: Chain,
+    wallet: &Address,
+    tx: &Value,
+) -> Result<Transaction, WalletError> {
+    let timestamp = tx["timeStamp"]
+        .as_str()
+        .ok_or(WalletError::Missing {
+            field: "timeStamp".to_string(),
+        })?
+        .to_string();
+    let from = tx["from"]
+        .as_str()
+        .ok_or(WalletError::Missing {
+            field: "from".to_string(),
+        })?
+        .to_string();
+    // Empty for contract creations.
+    let to = tx["to"]
+        .as_str()
+        .ok_or(WalletError::Missing {
+            field: "to".to_string(),
+        })?
+        .to_string();
+
+    Ok(Transaction {
+        chain,
+        hash: tx["hash"]
+            .as_str()
+            .ok_or(WalletError::Missing {
+                field: "hash".to_string(),
+            })?
+            .to_string(),
+        block_number: etherscan::block_number(tx)?,
+        timestamp: timestamp.parse::<u64>()?,
+        direction: Direction::of(wallet.as_str(), &from, &to),
+        from,
+        to,
+        gas_used: tx["gasUsed"]
+            .as_str()
+            .ok_or(WalletError::Missing {
+                field: "gasUsed".to_string(),
+            })?
+            .parse::<u64>()?,
+        // Etherscan reports the effective price in `gasPrice`; compatible
+        // explorers may add `effectiveGasPrice`, which is preferred when present.
+        gas_price: Wei::from_dec_str(
+            tx["effectiveGasPrice"]
+                .as_str()
+                .or(tx["gasPrice"].as_str())
+                .ok_or(WalletError::Missing {
+                    field: "gasPrice".to_string(),
+                })?,
+        )?,
+        quantity: Wei::from_dec_str(tx["value"].as_str().ok_or(WalletError::Missing {
+            field: "value".to_string(),
+        })?)?,
+        date: date_utils::epoch_converter(timestamp),
+        internal: false,
+        // `isError` flags a revert during execution; `txreceipt_status` is
+        // the receipt status, `0` for failure, and is empty before Byzantium.
+        is_error: tx["isError"].as_str() == Some("1")
+            || tx["txreceipt_status"].as_str() == Some("0"),
+        trace_id: String::new(),
+        fiat_values: Vec::new(),
+        fee_fiat_values: Vec::new(),
+    })
+}
+
+/// Returns the fees paid by a wallet over its recent transactions.
+///
+/// The transactions are valued first, so fees are also given in fiat at the
+/// price of each transaction's day.
 ///
 /// # Arguments
+/// * `provider` - JSON-RPC provider.
+/// * `store` - Local transaction cache.
+/// * `prices` - Price sources for historical prices.
+/// * `chain` - Chain to query.
 /// * `wallet` - Wallet address.
 /// * `transactions_offset` - Number of transactions to include.
-pub async fn get_average_gas(wallet: &str, transactions_offset: i32) -> Result<f64, WalletError> {
-    let transactions = get_transactions(wallet, transactions_offset).await?;
+/// * `currencies` - Currencies to express the fees in.
+pub async fn get_fee_summary(
+    provider: &Provider,
+    store: &TransactionStore,
+    prices: &PriceOracle,
+    chain: Chain,
+    wallet: &Address,
+    transactions_offset: i32,
+    currencies: &[Currency],
+) -> Result<FeeSummary, WalletError> {
+    let mut transactions =
+        get_transactions(provider, store, chain, wallet, transactions_offset).await?;
+    value_transactions(store, prices, chain, &mut transactions, currencies).await?;
 
-    let total_gas: i32 = transactions
+    Ok(fee_summary(chain, &transactions, currencies))
+}
+
+/// Sums the fees the wallet paid across the given transactions.
+///
+/// Only transactions sent by the wallet are counted: the sender pays the
+/// fee, and internal transfers have none. A currency is only totalled when
+/// every counted transaction was valued in it.
+pub fn fee_summary(
+    chain: Chain,
+    transactions: &[Transaction],
+    currencies: &[Currency],
+) -> FeeSummary {
+    let paid: Vec<&Transaction> = transactions
         .iter()
-        .map(|tx| tx.gas.parse::<i32>().unwrap_or(0))
-        .sum();
+        .filter(|tx| !tx.internal && tx.direction.is_sent())
+        .collect();
 
-    Ok(total_gas as f64 / transactions.len() as f64)
+    let total_fees: Wei = paid.iter().map(|tx| tx.fee()).sum();
+    let total_gas: u64 = paid.iter().map(|tx| tx.gas_used).sum();
+
+    let total_fiat = currencies
+        .iter()
+        .filter_map(|currency| {
+            paid.iter()
+                .map(|tx| {
+                    tx.fee_fiat_values
+                        .iter()
+                        .find(|value| &value.currency == currency)
+                        .map(|value| value.amount)
+                })
+                .sum::<Option<f64>>()
+                .map(|amount| FiatAmount {
+                    currency: currency.clone(),
+                    amount,
+                })
+        })
+        .collect();
+
+    FeeSummary {
+        chain,
+        transactions: transactions.len(),
+        paid_transactions: paid.len(),
+        total_fees,
+        average_fee: Wei::average(total_fees, paid.len()),
+        average_gas_used: match paid.len() {
+            0 => 0.0,
+            count => total_gas as f64 / count as f64,
+        },
+        // Weighted by gas, so it is the price actually paid per unit on average.
+        average_gas_price: Wei::average(total_fees, total_gas as usize),
+        total_fiat,
+    }
 }
 
-/// Returns the average ETH transferred in transactions of a wallet.
-pub async fn get_average_eth(wallet: &str, transactions_offset: i32) -> Result<f64, WalletError> {
-    let transactions = get_transactions(wallet, transactions_offset).await?;
+/// Returns the average gas used across the given transactions.
+///
+/// Internal transactions are skipped, since their gas is paid by the parent
+/// transaction. Returns 0 when there are no normal transactions.
+pub fn average_gas(transactions: &[Transaction]) -> f64 {
+    let normal: Vec<&Transaction> = transactions.iter().filter(|tx| !tx.internal).collect();
+    let total_gas: u64 = normal.iter().map(|tx| tx.gas_used).sum();
 
-    let total_eth: f64 = transactions.iter().map(|tx| tx.quantity).sum();
-    Ok(total_eth / transactions.len() as f64)
+    match normal.len() {
+        0 => 0.0,
+        count => total_gas as f64 / count as f64,
+    }
 }
 
-/// Generates wallet statistics (transaction count, averages, first activity).
-pub async fn generate_statistics(wallet: &str) -> Result<Statistics, WalletError> {
-    let tx_count = get_total_transactions(wallet).await?;
-    let average_gas = get_average_gas(wallet, (tx_count + 1) as i32).await?;
-    let average_eth = get_average_eth(wallet, (tx_count + 1) as i32).await?;
-    let first_transaction = get_first_transaction_date(wallet).await?;
+/// Returns the average native currency transferred across the given transactions.
+///
+/// Reverted transactions count towards the average with no value.
+pub fn average_eth(transactions: &[Transaction]) -> Wei {
+    let total_eth: Wei = transactions
+        .iter()
+        .filter(|tx| !tx.is_error)
+        .map(|tx| tx.quantity)
+        .sum();
+    Wei::average(total_eth, transactions.len())
+}
+
+/// Generates wallet statistics (transaction count, averages, first activity,
+/// token transfers) over the wallet's complete history.
+pub async fn generate_statistics(
+    provider: &Provider,
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+) -> Result<Statistics, WalletError> {
+    let transactions = get_all_transactions(provider, store, chain, wallet).await?;
+    let token_transfers = erc20::get_token_transfers(store, chain, wallet, None).await?;
+    statistics_from_transactions(chain, wallet, &transactions, &token_transfers)
+}
+
+/// Computes wallet statistics from an already fetched transaction history.
+///
+/// # Arguments
+/// * `chain` - Chain the history belongs to.
+/// * `wallet` - Wallet address the history belongs to.
+/// * `transactions` - Transactions sorted from oldest to newest.
+/// * `token_transfers` - ERC-20 token transfers of the wallet.
+///
+/// # Errors
+/// Returns [`WalletError::NoTransactions`] if the wallet has no transactions.
+pub fn statistics_from_transactions(
+    chain: Chain,
+    wallet: &Address,
+    transactions: &[Transaction],
+    token_transfers: &[TokenTransfer],
+) -> Result<Statistics, WalletError> {
+    let first = transactions.first().ok_or(WalletError::NoTransactions)?;
+
+    let mut tokens: Vec<&str> = token_transfers
+        .iter()
+        .map(|transfer| transfer.contract.as_str())
+        .collect();
+    tokens.sort();
+    tokens.dedup();
+
+    // Reverted transactions moved no value, and unrelated ones did not touch the balance.
+    let moves = |tx: &Transaction, outflow: bool| {
+        !tx.is_error
+            && match outflow {
+                true => tx.direction.is_outflow(),
+                false => tx.direction.is_inflow(),
+            }
+    };
+    let flow = |outflow: bool| -> Wei {
+        transactions
+            .iter()
+            .filter(|tx| moves(tx, outflow))
+            .map(|tx| tx.quantity)
+            .sum()
+    };
+
+    let transfers: Vec<&Transaction> = transactions
+        .iter()
+        .filter(|tx| tx.quantity != Wei::ZERO && (moves(tx, true) || moves(tx, false)))
+        .collect();
+    let mut values: Vec<Wei> = transfers.iter().map(|tx| tx.quantity).collect();
+    values.sort();
+
+    let largest = |outflow: bool| {
+        transfers
+            .iter()
+            .filter(|tx| moves(tx, outflow))
+            .max_by_key(|tx| tx.quantity)
+            .map(|tx| NotableTransfer {
+                hash: tx.hash.clone(),
+                quantity: tx.quantity,
+                date: utc_date(tx.timestamp),
+            })
+    };
+
+    // Activity covers token transfers too, which are not always part of the native history.
+    let timestamps: Vec<u64> = transactions
+        .iter()
+        .map(|tx| tx.timestamp)
+        .chain(token_transfers.iter().map(|transfer| transfer.timestamp))
+        .collect();
+    let active_days: HashSet<NaiveDate> = timestamps.iter().map(|&ts| utc_date(ts)).collect();
+
+    let counterparties: HashSet<String> = transactions
+        .iter()
+        .map(|tx| (tx.direction, tx.from.as_str(), tx.to.as_str()))
+        .chain(token_transfers.iter().map(|transfer| {
+            (
+                transfer.direction,
+                transfer.from.as_str(),
+                transfer.to.as_str(),
+            )
+        }))
+        .filter_map(|(direction, from, to)| match direction {
+            Direction::In => Some(from.to_lowercase()),
+            Direction::Out => Some(to.to_lowercase()),
+            Direction::SelfTransfer | Direction::ContractCreation | Direction::Unrelated => None,
+        })
+        .collect();
 
     Ok(Statistics {
-        address: wallet.to_string(),
-        total_transactions: tx_count.to_string(),
-        average_gas,
-        average_eth,
-        first_transaction,
+        chain,
+        address: wallet.clone(),
+        total_transactions: transactions.len(),
+        average_gas: average_gas(transactions),
+        average_eth: average_eth(transactions),
+        median_value: percentile(&values, 50.0),
+        p90_value: percentile(&values, 90.0),
+        largest_inflow: largest(false),
+        largest_outflow: largest(true),
+        total_inflow: flow(false),
+        total_outflow: flow(true),
+        first_transaction: utc_date(first.timestamp),
+        last_activity: utc_date(timestamps.iter().copied().max().unwrap_or(first.timestamp)),
+        active_days: active_days.len(),
+        unique_counterparties: counterparties.len(),
+        total_token_transfers: token_transfers.len(),
+        distinct_tokens: tokens.len(),
     })
 }
 
-/// Returns the total number of transactions for a wallet.
-pub async fn get_total_transactions(wallet: &str) -> Result<i64, WalletError> {
-    let client = Client::new();
-    let url = format!("https://api.blockcypher.com/v1/eth/main/addrs/{}", wallet);
-    let response = client.get(url).send().await?.json::<Value>().await?;
+/// Returns the nearest-rank percentile of sorted values, or zero when there are none.
+///
+/// # Arguments
+/// * `sorted` - Values sorted in ascending order.
+/// * `percent` - Percentile between 0 and 100.
+pub fn percentile(sorted: &[Wei], percent: f64) -> Wei {
+    if sorted.is_empty() {
+        return Wei::ZERO;
+    }
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
 
-    response["n_tx"].as_i64().ok_or(WalletError::Missing {
-        field: "n_tx".to_string(),
-    })
+/// Returns the UTC day of a Unix timestamp.
+pub fn utc_date(timestamp: u64) -> NaiveDate {
+    DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .date_naive()
+}
+
+/// Rebuilds the balance history of a wallet from its complete transaction history.
+///
+/// The series runs from the period of the first transaction to today.
+///
+/// # Arguments
+/// * `provider` - JSON-RPC provider of the chain.
+/// * `store` - Local transaction cache.
+/// * `chain` - Chain to query.
+/// * `wallet` - Wallet address.
+/// * `interval` - Spacing of the points.
+pub async fn get_balance_timeline(
+    provider: &Provider,
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+    interval: SamplingInterval,
+) -> Result<BalanceTimeline, WalletError> {
+    let transactions = get_all_transactions(provider, store, chain, wallet).await?;
+    Ok(balance_history::balance_timeline(
+        chain,
+        wallet,
+        &transactions,
+        interval,
+        Utc::now().date_naive(),
+    ))
+}
+
+/// Returns the counterparties of a wallet over its complete history.
+///
+/// Only the reported counterparties are checked for contract code.
+///
+/// # Arguments
+/// * `provider` - JSON-RPC provider of the chain.
+/// * `store` - Local transaction cache.
+/// * `chain` - Chain to query.
+/// * `wallet` - Wallet address.
+/// * `sort` - Order of the counterparties.
+/// * `limit` - Maximum number of counterparties to return, or `None` for all of them.
+pub async fn get_counterparties(
+    provider: &Provider,
+    store: &TransactionStore,
+    chain: Chain,
+    wallet: &Address,
+    sort: CounterpartySort,
+    limit: Option<usize>,
+) -> Result<Vec<Counterparty>, WalletError> {
+    let transactions = get_all_transactions(provider, store, chain, wallet).await?;
+    let mut counterparties = counterparties(&transactions, sort);
+    if let Some(limit) = limit {
+        counterparties.truncate(limit);
+    }
+
+    check_contracts(provider, store, chain, &mut counterparties).await?;
+    Ok(counterparties)
 }
 
-/// Returns the date of the first transaction of a wallet.
+/// Fills in whether each counterparty is a contract, from the cache or with `eth_getCode`.
 ///
-/// The date is returned in `YYYY-MM-DD` format.
-pub async fn get_first_transaction_date(wallet: &str) -> Result<String, WalletError> {
-    let transactions = get_transactions(wallet, 1).await?;
+/// A failed check leaves the counterparty unknown rather than failing.
+pub async fn check_contracts(
+    provider: &Provider,
+    store: &TransactionStore,
+    chain: Chain,
+    counterparties: &mut [Counterparty],
+) -> Result<(), WalletError> {
+    let mut unchecked = 0;
+    for counterparty in counterparties.iter_mut() {
+        counterparty.is_contract = match store.load_is_contract(chain, &counterparty.address)? {
+            Some(is_contract) => Some(is_contract),
+            None => match is_contract(provider, &counterparty.address).await {
+                Ok(is_contract) => {
+                    store.save_is_contract(chain, &counterparty.address, is_contract)?;
+                    Some(is_contract)
+                }
+                Err(_) => {
+                    unchecked += 1;
+                    None
+                }
+            },
+        };
+    }
+
+    if unchecked > 0 {
+        eprintln!(
+            "Could not check whether {} counterparties are contracts",
+            unchecked
+        );
+    }
+    Ok(())
+}
+
+/// Groups transactions by counterparty address.
+///
+/// The counterparty is the sender of incoming transfers and the recipient of
+/// outgoing ones; self-transfers and contract deployments have none. A
+/// reverted transaction counts as an interaction but adds no volume.
+///
+/// # Arguments
+/// * `transactions` - Transactions sorted from oldest to newest.
+/// * `sort` - Order of the returned counterparties, largest first.
+pub fn counterparties(transactions: &[Transaction], sort: CounterpartySort) -> Vec<Counterparty> {
+    let mut counterparties: Vec<Counterparty> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for tx in transactions {
+        let address = match tx.direction {
+            Direction::In => tx.from.to_lowercase(),
+            Direction::Out => tx.to.to_lowercase(),
+            Direction::SelfTransfer | Direction::ContractCreation | Direction::Unrelated => {
+                continue;
+            }
+        };
+        let date = utc_date(tx.timestamp);
+
+        let position = *positions.entry(address.clone()).or_insert_with(|| {
+            counterparties.push(Counterparty {
+                address,
+                transactions: 0,
+                volume_in: Wei::ZERO,
+                volume_out: Wei::ZERO,
+                first_interaction: date,
+                last_interaction: date,
+                is_contract: None,
+            });
+            counterparties.len() - 1
+        });
+
+        let counterparty = &mut counterparties[position];
+        counterparty.transactions += 1;
+        counterparty.last_interaction = date;
+        if tx.is_error {
+            continue;
+        }
+        match tx.direction {
+            Direction::In => counterparty.volume_in += tx.quantity,
+            _ => counterparty.volume_out += tx.quantity,
+        }
+    }
+
+    counterparties.sort_by(|a, b| {
+        let order = match sort {
+            CounterpartySort::Count => b.transactions.cmp(&a.transactions),
+            CounterpartySort::VolumeIn => b.volume_in.cmp(&a.volume_in),
+            CounterpartySort::VolumeOut => b.volume_out.cmp(&a.volume_out),
+            CounterpartySort::Volume => {
+                (b.volume_in + b.volume_out).cmp(&(a.volume_in + a.volume_out))
+            }
+            CounterpartySort::Recent => b.last_interaction.cmp(&a.last_interaction),
+        };
+        order
+            .then(b.transactions.cmp(&a.transactions))
+            .then(a.address.cmp(&b.address))
+    });
+    counterparties
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01 00:00:00 UTC.
+    const JAN_1: u64 = 1_704_067_200;
+
+    fn reverted(tx: Transaction) -> Transaction {
+        Transaction {
+            is_error: true,
+            ..tx
+        }
+    }
+
+    #[test]
+    fn average_gas_skips_internal_transactions() {
+        assert_eq!(average_gas(&[]), 0.0);
+
+        let mut internal = Transaction::received(100, JAN_1 + 60);
+        internal.internal = true;
+        internal.gas_used = 90_000;
+        assert_eq!(average_gas(std::slice::from_ref(&internal)), 0.0);
+
+        let transactions = [
+            Transaction::sent(Direction::Out, 100, 21_000, JAN_1),
+            internal,
+            reverted(Transaction::sent(Direction::Out, 100, 51_000, JAN_1 + 120)),
+        ];
+        assert_eq!(average_gas(&transactions), 36_000.0);
+    }
+
+    #[test]
+    fn fee_summary_of_no_transactions_is_zero() {
+        let summary = fee_summary(Chain::Ethereum, &[], &[Currency::default()]);
+
+        assert_eq!(summary.transactions, 0);
+        assert_eq!(summary.paid_transactions, 0);
+        assert_eq!(summary.total_fees, Wei::ZERO);
+        assert_eq!(summary.average_fee, Wei::ZERO);
+        assert_eq!(summary.average_gas_used, 0.0);
+        assert_eq!(summary.average_gas_price, Wei::ZERO);
+        assert_eq!(summary.total_fiat.len(), 1);
+        assert_eq!(summary.total_fiat[0].amount, 0.0);
+    }
+
+    #[test]
+    fn fee_summary_counts_fees_of_reverted_transactions() {
+        let transactions = [
+            reverted(Transaction::sent(Direction::Out, 500, 2, JAN_1)),
+            reverted(Transaction::sent(
+                Direction::ContractCreation,
+                0,
+                4,
+                JAN_1 + 60,
+            )),
+            Transaction::received(1000, JAN_1 + 120),
+        ];
+
+        let summary = fee_summary(Chain::Ethereum, &transactions, &[]);
+
+        assert_eq!(summary.transactions, 3);
+        assert_eq!(summary.paid_transactions, 2);
+        assert_eq!(summary.total_fees, Wei::milliether(6));
+        assert_eq!(summary.average_fee, Wei::milliether(3));
+        assert_eq!(summary.average_gas_used, 3.0);
+        assert_eq!(summary.average_gas_price, Wei::milliether(1));
+    }
+
+    #[test]
+    fn fee_summary_totals_only_fully_valued_currencies() {
+        let mut valued = Transaction::sent(Direction::Out, 500, 2, JAN_1);
+        valued.fee_fiat_values.push(FiatAmount {
+            currency: Currency::default(),
+            amount: 5.0,
+        });
+        let unvalued = Transaction::sent(Direction::Out, 500, 2, JAN_1 + 60);
+
+        let transactions = [valued, unvalued];
+        let summary = fee_summary(Chain::Ethereum, &transactions[..1], &[Currency::default()]);
+        assert_eq!(summary.total_fiat.len(), 1);
+        assert_eq!(summary.total_fiat[0].amount, 5.0);
+
+        let summary = fee_summary(Chain::Ethereum, &transactions, &[Currency::default()]);
+        assert!(summary.total_fiat.is_empty());
+    }
+
+    #[test]
+    fn percentile_uses_the_nearest_rank() {
+        assert_eq!(percentile(&[], 50.0), Wei::ZERO);
 
-    match transactions.first() {
-        Some(first_tx) => {
-            let date_str = first_tx.date.split(' ').next().unwrap_or("");
-            Ok(date_str.to_string())
+        let values: Vec<Wei> = (1..=10).map(Wei::milliether).collect();
+        assert_eq!(percentile(&values, 0.0), Wei::milliether(1));
+        assert_eq!(percentile(&values, 10.0), Wei::milliether(1));
+        assert_eq!(percentile(&values, 11.0), Wei::milliether(2));
+        assert_eq!(percentile(&values, 50.0), Wei::milliether(5));
+        assert_eq!(percentile(&values, 90.0), Wei::milliether(9));
+        assert_eq!(percentile(&values, 100.0), Wei::milliether(10));
+
+        let single = [Wei::milliether(7)];
+        assert_eq!(percentile(&single, 0.0), Wei::milliether(7));
+        assert_eq!(percentile(&single, 100.0), Wei::milliether(7));
+    }
+
+    /// Builds a transfer of one token of `contract` exchanged with `counterparty`.
+    fn token(
+        direction: Direction,
+        contract: &str,
+        counterparty: &str,
+        timestamp: u64,
+    ) -> TokenTransfer {
+        let (from, to) = match direction {
+            Direction::Out => (Transaction::SAMPLE_WALLET, counterparty),
+            _ => (counterparty, Transaction::SAMPLE_WALLET),
+        };
+        TokenTransfer {
+            chain: Chain::Ethereum,
+            hash: format!("0x{:064x}", timestamp),
+            log_index: 0,
+            block_number: timestamp / 12,
+            timestamp,
+            contract: contract.to_string(),
+            symbol: "TKN".to_string(),
+            decimals: 18,
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: primitive_types::U256::exp10(18),
+            direction,
+            date: String::new(),
         }
-        None => Err(WalletError::NoTransactions),
+    }
+
+    fn statistics(transactions: &[Transaction], transfers: &[TokenTransfer]) -> Statistics {
+        let wallet = Address::parse(Transaction::SAMPLE_WALLET).unwrap();
+        statistics_from_transactions(Chain::Ethereum, &wallet, transactions, transfers).unwrap()
+    }
+
+    #[test]
+    fn statistics_need_transactions() {
+        let wallet = Address::parse(Transaction::SAMPLE_WALLET).unwrap();
+        assert!(matches!(
+            statistics_from_transactions(Chain::Ethereum, &wallet, &[], &[]),
+            Err(WalletError::NoTransactions)
+        ));
+    }
+
+    #[test]
+    fn statistics_count_only_transfers_that_moved_value() {
+        let transactions = [
+            Transaction::received(1000, JAN_1),
+            Transaction::sent(Direction::Out, 300, 1, JAN_1 + 60),
+            reverted(Transaction::sent(Direction::Out, 5000, 1, JAN_1 + 120)),
+            Transaction::sent(Direction::SelfTransfer, 400, 1, JAN_1 + 180),
+            Transaction::sample(Direction::Unrelated, Wei::milliether(9000), JAN_1 + 240),
+        ];
+
+        let stats = statistics(&transactions, &[]);
+
+        assert_eq!(stats.total_transactions, 5);
+        assert_eq!(stats.total_inflow, Wei::milliether(1000));
+        assert_eq!(stats.total_outflow, Wei::milliether(300));
+        assert_eq!(stats.net_flow(), "0.7");
+        assert_eq!(
+            stats.largest_inflow.unwrap().quantity,
+            Wei::milliether(1000)
+        );
+        assert_eq!(
+            stats.largest_outflow.unwrap().quantity,
+            Wei::milliether(300)
+        );
+        assert_eq!(stats.median_value, Wei::milliether(300));
+        assert_eq!(stats.p90_value, Wei::milliether(1000));
+        assert_eq!(stats.unique_counterparties, 1);
+        assert_eq!(stats.active_days, 1);
+    }
+
+    #[test]
+    fn statistics_of_reverted_transactions_only() {
+        let transactions = [
+            reverted(Transaction::sent(Direction::Out, 500, 1, JAN_1)),
+            reverted(Transaction::sent(Direction::Out, 700, 1, JAN_1 + 60)),
+        ];
+
+        let stats = statistics(&transactions, &[]);
+
+        assert_eq!(stats.total_transactions, 2);
+        assert_eq!(stats.total_inflow, Wei::ZERO);
+        assert_eq!(stats.total_outflow, Wei::ZERO);
+        assert_eq!(stats.average_eth, Wei::ZERO);
+        assert_eq!(stats.median_value, Wei::ZERO);
+        assert!(stats.largest_outflow.is_none());
+    }
+
+    #[test]
+    fn statistics_include_token_transfers_in_activity() {
+        const DAY: u64 = 86_400;
+        let usdc = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+        let dai = "0x6b175474e89094c44da98b954eedeac495271d0f";
+        let transfers = [
+            token(
+                Direction::In,
+                usdc,
+                "0x00000000000000000000000000000000000000cc",
+                JAN_1 + DAY,
+            ),
+            token(
+                Direction::Out,
+                usdc,
+                "0x00000000000000000000000000000000000000BB",
+                JAN_1 + 2 * DAY,
+            ),
+            token(
+                Direction::In,
+                dai,
+                "0x00000000000000000000000000000000000000dd",
+                JAN_1 + 3 * DAY,
+            ),
+        ];
+
+        let stats = statistics(&[Transaction::received(1000, JAN_1)], &transfers);
+
+        assert_eq!(stats.total_token_transfers, 3);
+        assert_eq!(stats.distinct_tokens, 2);
+        assert_eq!(stats.active_days, 4);
+        assert_eq!(stats.unique_counterparties, 3);
+        assert_eq!(stats.first_transaction, utc_date(JAN_1));
+        assert_eq!(stats.last_activity, utc_date(JAN_1 + 3 * DAY));
+    }
+
+    #[test]
+    fn reverted_transactions_count_without_volume() {
+        let mut failed = Transaction::sent(Direction::Out, 700, 1, JAN_1 + 60);
+        failed.is_error = true;
+        let transactions = [
+            Transaction::received(1000, JAN_1),
+            failed,
+            Transaction::sent(Direction::Out, 200, 1, JAN_1 + 120),
+            Transaction::sent(Direction::SelfTransfer, 50, 1, JAN_1 + 180),
+        ];
+
+        let counterparties = counterparties(&transactions, CounterpartySort::Count);
+
+        assert_eq!(counterparties.len(), 1);
+        let counterparty = &counterparties[0];
+        assert_eq!(
+            counterparty.address,
+            "0x00000000000000000000000000000000000000bb"
+        );
+        assert_eq!(counterparty.transactions, 3);
+        assert_eq!(counterparty.volume_in, Wei::milliether(1000));
+        assert_eq!(counterparty.volume_out, Wei::milliether(200));
     }
 }
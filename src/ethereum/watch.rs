@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::{self, MissedTickBehavior};
+
+use super::ether_account::EtherAccount;
+use super::internal;
+use super::utils::{get_block_number, sync_transactions};
+use crate::address::Address;
+use crate::errors::WalletError;
+use crate::store::{INTERNAL_TRANSACTIONS_KIND, TRANSACTIONS_KIND};
+use crate::structs::WatchCursor;
+use crate::wallet_traits;
+
+#[async_trait]
+impl wallet_traits::WalletWatch for EtherAccount {
+    /// Polls the chain and prints every new transaction of the watched wallets until Ctrl-C.
+    ///
+    /// Each poll asks the node for its latest block and, once it has moved,
+    /// syncs the history of every wallet from the explorer. Transactions are
+    /// reported in block order, and the position of the last one reported is
+    /// saved per wallet and history kind, so a restart picks up where the
+    /// previous run stopped, even after the cache was rebuilt. A wallet watched for the
+    /// first time starts from its current history. Errors are printed per
+    /// wallet and the wallet retried on the next poll.
+    ///
+    /// # Arguments
+    /// * `wallets` - Wallets to watch on the account's chain.
+    /// * `interval` - Time between two polls.
+    async fn watch(&self, wallets: &[Address], interval: Duration) -> Result<(), WalletError> {
+        let mut ticker = time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut last_block = None;
+
+        println!(
+            "Watching {} wallet(s) on {} every {}s, press Ctrl-C to stop",
+            wallets.len(),
+            self.chain,
+            interval.as_secs()
+        );
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = tokio::signal::ctrl_c() => {
+                    println!("Stopped watching");
+                    return Ok(());
+                }
+            }
+
+            match self.poll(wallets, last_block).await {
+                Ok(block) => last_block = Some(block),
+                Err(error) => error.display_error(),
+            }
+        }
+    }
+}
+
+impl EtherAccount {
+    /// Syncs the watched wallets if the chain moved past `last_block`, and
+    /// prints their transactions not reported yet.
+    ///
+    /// A wallet that fails to sync is reported and retried on the next poll,
+    /// without holding back the others.
+    ///
+    /// # Returns
+    /// * The latest block of the chain.
+    async fn poll(&self, wallets: &[Address], last_block: Option<u64>) -> Result<u64, WalletError> {
+        let block = get_block_number(&self.provider).await?;
+        if last_block.is_some_and(|last_block| block <= last_block) {
+            return Ok(block);
+        }
+
+        for wallet in wallets {
+            if let Err(error) = self.poll_wallet(wallet, block).await {
+                eprintln!("Failed to poll {}:", wallet);
+                error.display_error();
+            }
+        }
+
+        Ok(block)
+    }
+
+    /// Syncs one watched wallet and prints its transactions after its watch cursors.
+    ///
+    /// Normal and internal transactions have a cursor each, since the
+    /// explorer can index an internal transaction after a normal one of a
+    /// later block was reported. A history kind watched for the first time
+    /// only gets its cursor set to the end of its current history.
+    async fn poll_wallet(&self, wallet: &Address, block: u64) -> Result<(), WalletError> {
+        sync_transactions(&self.store, self.chain, wallet).await?;
+        internal::sync_internal_transactions(&self.provider, &self.store, self.chain, wallet)
+            .await?;
+
+        let mut new_transactions = Vec::new();
+        let mut started = false;
+        for kind in [TRANSACTIONS_KIND, INTERNAL_TRANSACTIONS_KIND] {
+            match self.store.load_watch_cursor(self.chain, wallet, kind)? {
+                Some(cursor) => new_transactions.extend(
+                    self.store
+                        .load_transactions_after(self.chain, wallet, kind, &cursor)?,
+                ),
+                None => {
+                    let cursor = self
+                        .store
+                        .last_transaction_cursor(self.chain, wallet, kind)?;
+                    self.store
+                        .save_watch_cursor(self.chain, wallet, kind, &cursor)?;
+                    started = true;
+                }
+            }
+        }
+        if started {
+            println!("Watching {} from block {}", wallet, block);
+        }
+        if new_transactions.is_empty() {
+            return Ok(());
+        }
+        new_transactions.sort_by_key(WatchCursor::of);
+
+        let addresses: Vec<&str> = new_transactions
+            .iter()
+            .flat_map(|tx| [tx.from.as_str(), tx.to.as_str()])
+            .collect();
+        let names = self.ens.reverse_resolve_all(&addresses).await;
+        for tx in &new_transactions {
+            println!(
+                "New transaction of {} in block {}:",
+                wallet, tx.block_number
+            );
+            tx.display_detailed(&names);
+            let kind = match tx.internal {
+                true => INTERNAL_TRANSACTIONS_KIND,
+                false => TRANSACTIONS_KIND,
+            };
+            self.store
+                .save_watch_cursor(self.chain, wallet, kind, &WatchCursor::of(tx))?;
+        }
+
+        Ok(())
+    }
+}
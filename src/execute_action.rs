@@ -1,9 +1,14 @@
-use std::io::{stdin, stdout, Write};
+use std::io::{Write, stdin, stdout};
+use std::path::Path;
+use std::time::Duration;
 
 use crate::{
+    cli::{Command, DEFAULT_COUNTERPARTY_LIMIT},
+    dashboard::{DEFAULT_DASHBOARD_LIMIT, DEFAULT_REFRESH_SECS, run_dashboard},
     enums::Action,
     errors::WalletError,
-    wallet_traits::{WalletDisplay, WalletExport},
+    input,
+    wallet_traits::{WalletDashboard, WalletDisplay, WalletExport, WalletWatch},
 };
 
 /// Prompts the user to select an action from a list of wallet operations.
@@ -17,10 +22,17 @@ pub fn action_input() -> Action {
     println!("1. Balance");
     println!("2. Fiat");
     println!("3. Transactions");
-    println!("4. Average Gas");
+    println!("4. Fees");
     println!("5. Statistics");
     println!("6. Export");
-    println!("7. Exit");
+    println!("7. Tokens");
+    println!("8. NFTs");
+    println!("9. Cost Basis");
+    println!("10. Counterparties");
+    println!("11. Balance History");
+    println!("12. Charts");
+    println!("13. Dashboard");
+    println!("14. Exit");
 
     stdout().flush().unwrap();
 
@@ -37,7 +49,14 @@ pub fn action_input() -> Action {
             "4\n" => return Action::Gas,
             "5\n" => return Action::Stats,
             "6\n" => return Action::Export,
-            "7\n" => return Action::Exit,
+            "7\n" => return Action::Tokens,
+            "8\n" => return Action::Nfts,
+            "9\n" => return Action::CostBasis,
+            "10\n" => return Action::Counterparties,
+            "11\n" => return Action::BalanceHistory,
+            "12\n" => return Action::Chart,
+            "13\n" => return Action::Dashboard,
+            "14\n" => return Action::Exit,
             _ => {
                 println!("Please type a valid option");
                 action.clear();
@@ -47,7 +66,7 @@ pub fn action_input() -> Action {
 }
 
 /// Executes corresponding action based on user input
-pub async fn execute_action<T: WalletDisplay + WalletExport>(
+pub async fn execute_action<T: WalletDisplay + WalletExport + WalletDashboard>(
     account: T,
 ) -> Result<(), WalletError> {
     loop {
@@ -60,17 +79,52 @@ pub async fn execute_action<T: WalletDisplay + WalletExport>(
             Action::Fiat => {
                 account.display_fiat().await?;
             }
+            Action::Tokens => {
+                account.display_token_balances().await?;
+            }
+            Action::Nfts => {
+                account.display_nfts().await?;
+            }
             Action::Transactions => {
-                account.display_transactions().await?;
+                let transactions_offset = input::get_transaction_offset();
+                account.display_transactions(transactions_offset).await?;
             }
             Action::Gas => {
-                account.display_average_gas().await?;
+                let transactions_offset = input::get_transaction_offset();
+                account.display_fees(transactions_offset).await?;
             }
             Action::Stats => {
                 account.display_statistics().await?;
             }
+            Action::CostBasis => {
+                account.display_cost_basis().await?;
+            }
+            Action::Counterparties => {
+                let sort = input::get_counterparty_sort();
+                account
+                    .display_counterparties(sort, DEFAULT_COUNTERPARTY_LIMIT)
+                    .await?;
+            }
+            Action::BalanceHistory => {
+                let interval = input::get_sampling_interval();
+                account.display_balance_history(interval).await?;
+            }
+            Action::Chart => {
+                let kind = input::get_chart_kind();
+                let interval = input::get_sampling_interval();
+                account.display_chart(kind, interval).await?;
+            }
+            Action::Dashboard => {
+                run_dashboard(
+                    &account,
+                    DEFAULT_DASHBOARD_LIMIT,
+                    Duration::from_secs(DEFAULT_REFRESH_SECS),
+                )
+                .await?;
+            }
             Action::Export => {
-                account.export_to_csv().await?;
+                let format = input::get_export_format();
+                account.export_to_csv(Path::new("."), format).await?;
             }
             Action::Exit => {
                 std::process::exit(0);
@@ -78,3 +132,38 @@ pub async fn execute_action<T: WalletDisplay + WalletExport>(
         }
     }
 }
+
+/// Executes a single non-interactive subcommand and returns.
+pub async fn run_command<T: WalletDisplay + WalletExport + WalletDashboard + WalletWatch>(
+    account: T,
+    command: Command,
+) -> Result<(), WalletError> {
+    match command {
+        Command::Balance { .. } => account.display_balance().await,
+        Command::Fiat { .. } => account.display_fiat().await,
+        Command::Tokens { .. } => account.display_token_balances().await,
+        Command::Nfts { .. } => account.display_nfts().await,
+        Command::Transactions { limit, .. } => account.display_transactions(limit).await,
+        Command::Gas { limit, .. } => account.display_fees(limit).await,
+        Command::Stats { .. } => account.display_statistics().await,
+        Command::CostBasis { .. } => account.display_cost_basis().await,
+        Command::Counterparties {
+            sort, limit, out, ..
+        } => match out {
+            Some(path) => account.export_counterparties(&path, sort, limit).await,
+            None => account.display_counterparties(sort, limit).await,
+        },
+        Command::BalanceHistory { interval, out, .. } => match out {
+            Some(path) => account.export_balance_history(&path, interval).await,
+            None => account.display_balance_history(interval).await,
+        },
+        Command::Chart { kind, interval, .. } => account.display_chart(kind, interval).await,
+        Command::Dashboard { limit, refresh, .. } => {
+            run_dashboard(&account, limit, Duration::from_secs(refresh)).await
+        }
+        Command::Watch {
+            wallets, interval, ..
+        } => account.watch(&wallets, Duration::from_secs(interval)).await,
+        Command::Export { out, format, .. } => account.export_to_csv(&out, format).await,
+    }
+}
@@ -1,33 +1,55 @@
 use std::io::stdin;
 
+use clap::ValueEnum;
+
+use crate::{
+    address::Address, balance_history::SamplingInterval, charts::ChartKind,
+    enums::CounterpartySort, ethereum::ens::EnsResolver, tax::ExportFormat,
+};
+
 /// Maximum number of transactions that can be fetched/displayed at once.
 const MAX_TRANSACTION_OFFSET: i32 = 20;
 
-/// Prompts the user to input a wallet address via standard input.
+/// Prompts the user to input a wallet address or ENS name via standard input.
+///
+/// ENS names such as `vitalik.eth` are resolved through `ens`. Asks again
+/// until the input is a valid [`Address`] or a resolvable name. Exits the
+/// program when standard input is closed.
 ///
 /// # Returns
-/// * A `String` containing the wallet address.
-pub fn get_wallet() -> String {
+/// * The parsed or resolved wallet address.
+pub async fn get_wallet(ens: &EnsResolver) -> Address {
     let mut wallet = String::new();
 
     println!("Please input your wallet");
 
     loop {
-        stdin()
+        wallet.clear();
+        let read = stdin()
             .read_line(&mut wallet)
             .expect("Please input a valid string");
+        if read == 0 {
+            std::process::exit(0);
+        }
 
-        // Remove trailing newline
-        wallet.truncate(wallet.len() - 1);
+        let address = if EnsResolver::is_name(&wallet) {
+            ens.resolve(&wallet).await
+        } else {
+            Address::parse(&wallet)
+        };
 
-        if !wallet.starts_with("0x") || wallet.len() != 42 {
-            println!("Please input a valid address\n");
-            println!("Input your wallet again");
-            wallet.clear();
-            continue;
+        match address {
+            Ok(address) => {
+                if EnsResolver::is_name(&wallet) {
+                    println!("{} resolves to {}", wallet.trim(), address);
+                }
+                return address;
+            }
+            Err(error) => {
+                error.display_error();
+                println!("Input your wallet again");
+            }
         }
-
-        return wallet;
     }
 }
 
@@ -60,3 +82,77 @@ pub fn get_transaction_offset() -> i32 {
         }
     }
 }
+
+/// Prompts the user to choose the layout of the exported files.
+///
+/// An empty answer selects the standard layout.
+///
+/// # Returns
+/// * The chosen [`ExportFormat`].
+pub fn get_export_format() -> ExportFormat {
+    choose("Export format")
+}
+
+/// Prompts the user to choose the order of the counterparties.
+///
+/// An empty answer sorts them by number of transactions.
+///
+/// # Returns
+/// * The chosen [`CounterpartySort`].
+pub fn get_counterparty_sort() -> CounterpartySort {
+    choose("Sort counterparties by")
+}
+
+/// Prompts the user to choose the spacing of a balance history or chart.
+///
+/// An empty answer selects one point per month.
+///
+/// # Returns
+/// * The chosen [`SamplingInterval`].
+pub fn get_sampling_interval() -> SamplingInterval {
+    choose("Sampling interval")
+}
+
+/// Prompts the user to choose the chart to draw.
+///
+/// An empty answer selects the balance chart.
+///
+/// # Returns
+/// * The chosen [`ChartKind`].
+pub fn get_chart_kind() -> ChartKind {
+    choose("Chart")
+}
+
+/// Prompts the user to pick one value of an enum by name, listing them all.
+///
+/// An empty answer selects the default value.
+fn choose<T: ValueEnum + Default>(prompt: &str) -> T {
+    let name = |value: &T| {
+        value
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    };
+    let names: Vec<String> = T::value_variants().iter().map(name).collect();
+
+    loop {
+        println!(
+            "{} ({}) [{}]: ",
+            prompt,
+            names.join(", "),
+            name(&T::default())
+        );
+
+        let mut input = String::new();
+        stdin().read_line(&mut input).expect("Failed to read line");
+
+        let input = input.trim();
+        if input.is_empty() {
+            return T::default();
+        }
+        match T::from_str(input, true) {
+            Ok(value) => return value,
+            Err(_) => println!("Please input one of: {}", names.join(", ")),
+        }
+    }
+}
@@ -1,24 +1,87 @@
 use std::error::Error;
 
+use address::Address;
+use clap::Parser;
+use cli::Cli;
+use config::Config;
+use ethereum::chain::Chain;
+use ethereum::ens::EnsResolver;
 use ethereum::ether_account::EtherAccount;
-use execute_action::execute_action;
-use tokio;
+use ethereum::provider::Provider;
+use execute_action::{execute_action, run_command};
+use prices::PriceOracle;
+use store::TransactionStore;
 
+pub mod address;
+pub mod amount;
+pub mod balance_history;
+pub mod charts;
+pub mod cli;
+pub mod config;
+pub mod cost_basis;
+pub mod currency;
+pub mod dashboard;
 pub mod date_utils;
 pub mod enums;
 pub mod errors;
 pub mod ethereum;
 pub mod execute_action;
 pub mod input;
+pub mod prices;
+pub mod store;
 pub mod structs;
+pub mod tax;
 pub mod wallet_traits;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let wallet: String = input::get_wallet();
+    let cli = Cli::parse();
+    let config = Config::load(&cli).inspect_err(|error| error.display_error())?;
+    let provider = Provider::new(config.rpc_url(config.chain));
+    let ens = EnsResolver::new(Provider::new(config.rpc_url(Chain::Ethereum)));
+    let prices = PriceOracle::from_sources(
+        &config.price_sources,
+        config.price_file.as_deref(),
+        config.price_history_file.as_deref(),
+        config.price_ttl,
+    )
+    .inspect_err(|error| error.display_error())?;
+    let store =
+        TransactionStore::open(&config.db_path).inspect_err(|error| error.display_error())?;
 
-    let ether_account = EtherAccount { wallet };
-    execute_action(ether_account).await?;
+    match cli.command {
+        Some(command) => {
+            let wallet = command.wallet().clone();
+            let ether_account = EtherAccount {
+                wallet,
+                chain: config.chain,
+                tokens: config.tokens.clone(),
+                provider,
+                ens,
+                prices,
+                currencies: config.currencies.clone(),
+                cost_basis_method: config.cost_basis_method,
+                store,
+            };
+            run_command(ether_account, command).await?;
+        }
+        None => {
+            let wallet: Address = input::get_wallet(&ens).await;
+
+            let ether_account = EtherAccount {
+                wallet,
+                chain: config.chain,
+                tokens: config.tokens.clone(),
+                provider,
+                ens,
+                prices,
+                currencies: config.currencies.clone(),
+                cost_basis_method: config.cost_basis_method,
+                store,
+            };
+            execute_action(ether_account).await?;
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,91 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use reqwest::Client;
+use serde_json::Value;
+
+use super::{PriceProvider, unavailable};
+use crate::errors::WalletError;
+
+/// Spot prices from the Coinbase exchange-rates endpoint.
+pub struct Coinbase {
+    client: Client,
+}
+
+impl Coinbase {
+    /// Creates a Coinbase price provider.
+    pub fn new() -> Self {
+        Coinbase {
+            client: Client::new(),
+        }
+    }
+}
+
+impl Default for Coinbase {
+    fn default() -> Self {
+        Coinbase::new()
+    }
+}
+
+#[async_trait]
+impl PriceProvider for Coinbase {
+    fn name(&self) -> &'static str {
+        "Coinbase"
+    }
+
+    async fn spot_price(&self, asset: &str, currency: &str) -> Result<f64, WalletError> {
+        let response = self
+            .client
+            .get(format!(
+                "https://api.coinbase.com/v2/exchange-rates?currency={}",
+                asset
+            ))
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        if let Some(message) = response["errors"][0]["message"].as_str() {
+            return Err(WalletError::PriceApi {
+                provider: self.name().to_string(),
+                message: message.to_string(),
+            });
+        }
+
+        let rate = response["data"]["rates"][currency]
+            .as_str()
+            .ok_or_else(|| unavailable(asset, currency))?;
+        Ok(rate.trim_end().parse::<f64>()?)
+    }
+
+    async fn historical_price(
+        &self,
+        asset: &str,
+        currency: &str,
+        date: NaiveDate,
+    ) -> Result<f64, WalletError> {
+        let response = self
+            .client
+            .get(format!(
+                "https://api.coinbase.com/v2/prices/{}-{}/spot?date={}",
+                asset,
+                currency,
+                date.format("%Y-%m-%d")
+            ))
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        if let Some(message) = response["errors"][0]["message"].as_str() {
+            return Err(WalletError::PriceApi {
+                provider: self.name().to_string(),
+                message: message.to_string(),
+            });
+        }
+
+        let amount = response["data"]["amount"]
+            .as_str()
+            .ok_or_else(|| unavailable(asset, currency))?;
+        Ok(amount.parse::<f64>()?)
+    }
+}
@@ -0,0 +1,137 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use reqwest::Client;
+use serde_json::Value;
+
+use super::{PriceProvider, unavailable};
+use crate::errors::WalletError;
+
+/// Spot prices from the CoinGecko simple price API.
+pub struct CoinGecko {
+    client: Client,
+}
+
+impl CoinGecko {
+    /// Creates a CoinGecko price provider.
+    pub fn new() -> Self {
+        CoinGecko {
+            client: Client::new(),
+        }
+    }
+
+    /// Maps a ticker to the CoinGecko coin id, or `None` for an asset it is not set up for.
+    fn coin_id(asset: &str) -> Option<&'static str> {
+        match asset {
+            "ETH" => Some("ethereum"),
+            "POL" => Some("polygon-ecosystem-token"),
+            "BNB" => Some("binancecoin"),
+            _ => None,
+        }
+    }
+
+    /// Returns the error message of a CoinGecko response, if it is one.
+    fn error_message(response: &Value) -> Option<&str> {
+        response["error"]
+            .as_str()
+            .or_else(|| response["status"]["error_message"].as_str())
+    }
+}
+
+impl Default for CoinGecko {
+    fn default() -> Self {
+        CoinGecko::new()
+    }
+}
+
+#[async_trait]
+impl PriceProvider for CoinGecko {
+    fn name(&self) -> &'static str {
+        "CoinGecko"
+    }
+
+    async fn spot_price(&self, asset: &str, currency: &str) -> Result<f64, WalletError> {
+        let id = CoinGecko::coin_id(asset).ok_or_else(|| unavailable(asset, currency))?;
+        let vs_currency = currency.to_lowercase();
+
+        let response = self
+            .client
+            .get(format!(
+                "https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies={}",
+                id, vs_currency
+            ))
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        if let Some(message) = CoinGecko::error_message(&response) {
+            return Err(WalletError::PriceApi {
+                provider: self.name().to_string(),
+                message: message.to_string(),
+            });
+        }
+
+        response[id][&vs_currency]
+            .as_f64()
+            .ok_or_else(|| unavailable(asset, currency))
+    }
+
+    async fn historical_price(
+        &self,
+        asset: &str,
+        currency: &str,
+        date: NaiveDate,
+    ) -> Result<f64, WalletError> {
+        let id = CoinGecko::coin_id(asset).ok_or_else(|| unavailable(asset, currency))?;
+        let vs_currency = currency.to_lowercase();
+
+        let response = self
+            .client
+            .get(format!(
+                "https://api.coingecko.com/api/v3/coins/{}/history?date={}&localization=false",
+                id,
+                date.format("%d-%m-%Y")
+            ))
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        if let Some(message) = CoinGecko::error_message(&response) {
+            return Err(WalletError::PriceApi {
+                provider: self.name().to_string(),
+                message: message.to_string(),
+            });
+        }
+
+        response["market_data"]["current_price"][&vs_currency]
+            .as_f64()
+            .ok_or_else(|| unavailable(asset, currency))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn unknown_asset_has_no_price() {
+        assert!(matches!(
+            CoinGecko::new().spot_price("DOGE", "USD").await,
+            Err(WalletError::PriceUnavailable { asset, currency }) if asset == "DOGE" && currency == "USD"
+        ));
+    }
+
+    #[test]
+    fn reads_both_error_payloads() {
+        let not_found = json!({ "error": "coin not found" });
+        let rate_limited = json!({ "status": { "error_code": 429, "error_message": "Throttled" } });
+        let price = json!({ "ethereum": { "usd": 3000.0 } });
+
+        assert_eq!(CoinGecko::error_message(&not_found), Some("coin not found"));
+        assert_eq!(CoinGecko::error_message(&rate_limited), Some("Throttled"));
+        assert_eq!(CoinGecko::error_message(&price), None);
+    }
+}
@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use reqwest::Client;
+use serde_json::Value;
+
+use super::{PriceProvider, unavailable};
+use crate::errors::WalletError;
+
+/// Spot prices from the Kraken public ticker, using the last trade price.
+pub struct Kraken {
+    client: Client,
+}
+
+impl Kraken {
+    /// Creates a Kraken price provider.
+    pub fn new() -> Self {
+        Kraken {
+            client: Client::new(),
+        }
+    }
+}
+
+impl Default for Kraken {
+    fn default() -> Self {
+        Kraken::new()
+    }
+}
+
+#[async_trait]
+impl PriceProvider for Kraken {
+    fn name(&self) -> &'static str {
+        "Kraken"
+    }
+
+    async fn spot_price(&self, asset: &str, currency: &str) -> Result<f64, WalletError> {
+        let response = self
+            .client
+            .get(format!(
+                "https://api.kraken.com/0/public/Ticker?pair={}{}",
+                asset, currency
+            ))
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        if let Some(message) = response["error"]
+            .as_array()
+            .and_then(|errors| errors.first())
+        {
+            return Err(WalletError::PriceApi {
+                provider: self.name().to_string(),
+                message: message.as_str().unwrap_or_default().to_string(),
+            });
+        }
+
+        // The pair is returned under Kraken's own name, e.g. `XETHZUSD`.
+        let last_trade = response["result"]
+            .as_object()
+            .and_then(|pairs| pairs.values().next())
+            .and_then(|ticker| ticker["c"][0].as_str())
+            .ok_or_else(|| unavailable(asset, currency))?;
+        Ok(last_trade.parse::<f64>()?)
+    }
+
+    /// Uses the close of the daily candle. Kraken only serves the last 720 candles.
+    async fn historical_price(
+        &self,
+        asset: &str,
+        currency: &str,
+        date: NaiveDate,
+    ) -> Result<f64, WalletError> {
+        let day_start = date
+            .and_hms_opt(0, 0, 0)
+            .map_or(0, |start| start.and_utc().timestamp());
+
+        let response = self
+            .client
+            .get(format!(
+                "https://api.kraken.com/0/public/OHLC?pair={}{}&interval=1440&since={}",
+                asset,
+                currency,
+                day_start - 1
+            ))
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        // Candles are `[time, open, high, low, close, vwap, volume, count]`.
+        let close = response["result"]
+            .as_object()
+            .and_then(|result| result.values().find_map(|candles| candles.as_array()))
+            .and_then(|candles| {
+                candles
+                    .iter()
+                    .find(|candle| candle[0].as_i64() == Some(day_start))
+            })
+            .and_then(|candle| candle[4].as_str())
+            .ok_or_else(|| unavailable(asset, currency))?;
+        Ok(close.parse::<f64>()?)
+    }
+}
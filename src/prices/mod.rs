@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::errors::WalletError;
+
+pub mod coinbase;
+pub mod coingecko;
+pub mod kraken;
+pub mod price_history;
+pub mod static_prices;
+
+use coinbase::Coinbase;
+use coingecko::CoinGecko;
+use kraken::Kraken;
+use price_history::PriceHistoryFile;
+use static_prices::StaticPrices;
+
+/// Returns the error of a source that has no price for `asset` in `currency`.
+fn unavailable(asset: &str, currency: &str) -> WalletError {
+    WalletError::PriceUnavailable {
+        asset: asset.to_string(),
+        currency: currency.to_string(),
+    }
+}
+
+/// How long a fetched price is reused when no TTL is configured.
+pub const DEFAULT_PRICE_TTL: Duration = Duration::from_secs(60);
+
+/// A source of spot prices for the native currencies of the supported chains.
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    /// Short name of the source, used in messages.
+    fn name(&self) -> &'static str;
+
+    /// Returns the current price of one unit of `asset` in `currency`.
+    ///
+    /// # Arguments
+    /// * `asset` - Ticker of the priced asset, e.g. `ETH`.
+    /// * `currency` - ISO 4217 code of the quote currency, e.g. `USD`.
+    async fn spot_price(&self, asset: &str, currency: &str) -> Result<f64, WalletError>;
+
+    /// Returns the price of one unit of `asset` in `currency` on a past UTC day.
+    ///
+    /// Sources without price history keep the default, which always fails.
+    async fn historical_price(
+        &self,
+        asset: &str,
+        currency: &str,
+        _date: NaiveDate,
+    ) -> Result<f64, WalletError> {
+        Err(unavailable(asset, currency))
+    }
+
+    /// Whether [`historical_price`](PriceProvider::historical_price) answers
+    /// with the price of the requested day, rather than a price that does
+    /// not depend on the day.
+    fn serves_history(&self) -> bool {
+        true
+    }
+}
+
+/// Price of an asset on a UTC day, as returned by [`PriceOracle::historical_price`].
+#[derive(Debug, Clone, Copy)]
+pub struct DailyPrice {
+    /// Price of one unit of the asset.
+    pub price: f64,
+    /// Whether the price is final: it comes from a source with price history
+    /// and the day is over, so it can be cached for good.
+    pub settled: bool,
+}
+
+/// A price source that can be selected in the config file or on the CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSource {
+    /// Coinbase exchange rates.
+    Coinbase,
+    /// CoinGecko simple price API.
+    Coingecko,
+    /// Kraken public ticker.
+    Kraken,
+    /// Prices read from the local price file.
+    Static,
+    /// Daily prices read from the local price history file.
+    History,
+}
+
+/// Queries price providers in priority order and caches their answers.
+///
+/// A provider that fails is skipped and the next one is asked, so a single
+/// exchange being down does not break fiat valuation.
+pub struct PriceOracle {
+    providers: Vec<Box<dyn PriceProvider>>,
+    ttl: Duration,
+    cache: Mutex<HashMap<(String, String), (Instant, f64)>>,
+    history_cache: Mutex<HashMap<(String, String, NaiveDate), f64>>,
+}
+
+impl PriceOracle {
+    /// Creates an oracle asking `providers` in order, caching prices for `ttl`.
+    pub fn new(providers: Vec<Box<dyn PriceProvider>>, ttl: Duration) -> Self {
+        PriceOracle {
+            providers,
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+            history_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Builds an oracle from the configured price sources.
+    ///
+    /// # Arguments
+    /// * `sources` - Sources in priority order.
+    /// * `price_file` - CSV file backing the [`PriceSource::Static`] source.
+    /// * `price_history_file` - CSV file backing the [`PriceSource::History`] source.
+    /// * `ttl` - How long a fetched spot price is reused.
+    ///
+    /// # Errors
+    /// Returns an error if a file-backed source is selected without a readable file.
+    pub fn from_sources(
+        sources: &[PriceSource],
+        price_file: Option<&Path>,
+        price_history_file: Option<&Path>,
+        ttl: Duration,
+    ) -> Result<Self, WalletError> {
+        let mut providers: Vec<Box<dyn PriceProvider>> = Vec::new();
+        for source in sources {
+            match source {
+                PriceSource::Coinbase => providers.push(Box::new(Coinbase::new())),
+                PriceSource::Coingecko => providers.push(Box::new(CoinGecko::new())),
+                PriceSource::Kraken => providers.push(Box::new(Kraken::new())),
+                PriceSource::Static => {
+                    let path = price_file.ok_or(WalletError::Missing {
+                        field: "price_file".to_string(),
+                    })?;
+                    providers.push(Box::new(StaticPrices::from_file(path)?));
+                }
+                PriceSource::History => {
+                    let path = price_history_file.ok_or(WalletError::Missing {
+                        field: "price_history_file".to_string(),
+                    })?;
+                    providers.push(Box::new(PriceHistoryFile::from_file(path)?));
+                }
+            }
+        }
+
+        Ok(PriceOracle::new(providers, ttl))
+    }
+
+    /// Returns the price of one unit of `asset` in `currency`.
+    ///
+    /// Serves the cached price while it is younger than the TTL, otherwise
+    /// asks each provider in turn until one answers.
+    ///
+    /// # Errors
+    /// Returns [`WalletError::PriceUnavailable`] if no provider has a price.
+    pub async fn spot_price(&self, asset: &str, currency: &str) -> Result<f64, WalletError> {
+        let key = (asset.to_uppercase(), currency.to_uppercase());
+        if let Some((fetched, price)) = self.cache.lock().expect("price cache poisoned").get(&key)
+            && fetched.elapsed() < self.ttl
+        {
+            return Ok(*price);
+        }
+
+        for provider in &self.providers {
+            match provider.spot_price(&key.0, &key.1).await {
+                Ok(price) => {
+                    self.cache
+                        .lock()
+                        .expect("price cache poisoned")
+                        .insert(key, (Instant::now(), price));
+                    return Ok(price);
+                }
+                Err(error) => eprintln!(
+                    "{} price for {}/{} unavailable: {}",
+                    provider.name(),
+                    key.0,
+                    key.1,
+                    error
+                ),
+            }
+        }
+
+        Err(WalletError::PriceUnavailable {
+            asset: key.0,
+            currency: key.1,
+        })
+    }
+
+    /// Returns the price of one unit of `asset` in `currency` on a UTC day.
+    ///
+    /// Settled prices never change, so they are cached without expiry. The
+    /// price of the current day, or one from a source without price history,
+    /// is asked again every time.
+    ///
+    /// # Errors
+    /// Returns [`WalletError::PriceUnavailable`] if no provider has a price for that day.
+    pub async fn historical_price(
+        &self,
+        asset: &str,
+        currency: &str,
+        date: NaiveDate,
+    ) -> Result<DailyPrice, WalletError> {
+        let key = (asset.to_uppercase(), currency.to_uppercase(), date);
+        if let Some(price) = self
+            .history_cache
+            .lock()
+            .expect("price cache poisoned")
+            .get(&key)
+        {
+            return Ok(DailyPrice {
+                price: *price,
+                settled: true,
+            });
+        }
+
+        let past = date < Utc::now().date_naive();
+        for provider in &self.providers {
+            if let Ok(price) = provider.historical_price(&key.0, &key.1, date).await {
+                let settled = past && provider.serves_history();
+                if settled {
+                    self.history_cache
+                        .lock()
+                        .expect("price cache poisoned")
+                        .insert(key, price);
+                }
+                return Ok(DailyPrice { price, settled });
+            }
+        }
+
+        Err(WalletError::PriceUnavailable {
+            asset: key.0,
+            currency: key.1,
+        })
+    }
+}
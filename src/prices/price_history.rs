@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use csv::Reader;
+
+use super::{PriceProvider, unavailable};
+use crate::errors::WalletError;
+
+/// Daily prices read from a local CSV dataset, for valuing history offline.
+///
+/// The file has a `date,asset,currency,price` header with ISO dates:
+///
+/// ```text
+/// date,asset,currency,price
+/// 2024-03-01,ETH,EUR,3150.42
+/// ```
+///
+/// A day missing from the dataset is valued at the most recent earlier price.
+/// Days after the last one of the dataset have no price, so they are left to
+/// the next source instead of repeating a stale price.
+pub struct PriceHistoryFile {
+    prices: BTreeMap<(String, String, NaiveDate), f64>,
+}
+
+impl PriceHistoryFile {
+    /// Loads the prices of a CSV price history file.
+    pub fn from_file(path: &Path) -> Result<Self, WalletError> {
+        let mut reader = Reader::from_path(path)?;
+        let mut prices = BTreeMap::new();
+
+        for record in reader.records() {
+            let record = record?;
+            let field = |index: usize, name: &str| {
+                record
+                    .get(index)
+                    .map(str::trim)
+                    .ok_or(WalletError::Missing {
+                        field: name.to_string(),
+                    })
+            };
+
+            let date = field(0, "date")?;
+            let date =
+                NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| WalletError::Missing {
+                    field: format!("valid date in price history, got {}", date),
+                })?;
+            prices.insert(
+                (
+                    field(1, "asset")?.to_uppercase(),
+                    field(2, "currency")?.to_uppercase(),
+                    date,
+                ),
+                field(3, "price")?.parse::<f64>()?,
+            );
+        }
+
+        Ok(PriceHistoryFile { prices })
+    }
+
+    /// Returns the latest price of an asset in a currency on or before `until`,
+    /// with the day it was recorded for.
+    fn latest_price(
+        &self,
+        asset: &str,
+        currency: &str,
+        until: NaiveDate,
+    ) -> Option<(NaiveDate, f64)> {
+        let (asset, currency) = (asset.to_string(), currency.to_string());
+        self.prices
+            .range((asset.clone(), currency.clone(), NaiveDate::MIN)..=(asset, currency, until))
+            .next_back()
+            .map(|((_, _, day), price)| (*day, *price))
+    }
+}
+
+#[async_trait]
+impl PriceProvider for PriceHistoryFile {
+    fn name(&self) -> &'static str {
+        "price history file"
+    }
+
+    /// Uses the latest price of the dataset.
+    async fn spot_price(&self, asset: &str, currency: &str) -> Result<f64, WalletError> {
+        self.latest_price(asset, currency, NaiveDate::MAX)
+            .map(|(_, price)| price)
+            .ok_or_else(|| unavailable(asset, currency))
+    }
+
+    async fn historical_price(
+        &self,
+        asset: &str,
+        currency: &str,
+        date: NaiveDate,
+    ) -> Result<f64, WalletError> {
+        let covered = self
+            .latest_price(asset, currency, NaiveDate::MAX)
+            .is_some_and(|(last, _)| date <= last);
+
+        self.latest_price(asset, currency, date)
+            .filter(|_| covered)
+            .map(|(_, price)| price)
+            .ok_or_else(|| unavailable(asset, currency))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    fn history() -> PriceHistoryFile {
+        let prices = [(1, 3000.0), (3, 3300.0)]
+            .into_iter()
+            .map(|(d, price)| (("ETH".to_string(), "EUR".to_string(), day(d)), price))
+            .collect();
+        PriceHistoryFile { prices }
+    }
+
+    #[tokio::test]
+    async fn carries_prices_forward_inside_the_dataset() {
+        let history = history();
+        assert_eq!(
+            history
+                .historical_price("ETH", "EUR", day(1))
+                .await
+                .unwrap(),
+            3000.0
+        );
+        assert_eq!(
+            history
+                .historical_price("ETH", "EUR", day(2))
+                .await
+                .unwrap(),
+            3000.0
+        );
+        assert_eq!(
+            history
+                .historical_price("ETH", "EUR", day(3))
+                .await
+                .unwrap(),
+            3300.0
+        );
+    }
+
+    #[tokio::test]
+    async fn has_no_price_outside_the_dataset() {
+        let history = history();
+        for date in [day(4), NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()] {
+            assert!(matches!(
+                history.historical_price("ETH", "EUR", date).await,
+                Err(WalletError::PriceUnavailable { .. })
+            ));
+        }
+        assert!(
+            history
+                .historical_price("ETH", "USD", day(2))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn spot_price_is_the_last_price_of_the_dataset() {
+        assert_eq!(history().spot_price("ETH", "EUR").await.unwrap(), 3300.0);
+    }
+}
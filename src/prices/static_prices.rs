@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use csv::Reader;
+
+use super::{PriceProvider, unavailable};
+use crate::errors::WalletError;
+
+/// Fixed prices read from a local CSV file.
+///
+/// The file has an `asset,currency,price` header, for example:
+///
+/// ```text
+/// asset,currency,price
+/// ETH,USD,3000
+/// ```
+///
+/// Useful offline, in tests against a devnet, or to pin a reporting rate.
+/// The same prices are used for past dates.
+pub struct StaticPrices {
+    prices: HashMap<(String, String), f64>,
+}
+
+impl StaticPrices {
+    /// Loads the prices of a CSV price file.
+    pub fn from_file(path: &Path) -> Result<Self, WalletError> {
+        let mut reader = Reader::from_path(path)?;
+        let mut prices = HashMap::new();
+
+        for record in reader.records() {
+            let record = record?;
+            let field = |index: usize, name: &str| {
+                record.get(index).ok_or(WalletError::Missing {
+                    field: name.to_string(),
+                })
+            };
+
+            prices.insert(
+                (
+                    field(0, "asset")?.trim().to_uppercase(),
+                    field(1, "currency")?.trim().to_uppercase(),
+                ),
+                field(2, "price")?.trim().parse::<f64>()?,
+            );
+        }
+
+        Ok(StaticPrices { prices })
+    }
+}
+
+#[async_trait]
+impl PriceProvider for StaticPrices {
+    fn name(&self) -> &'static str {
+        "price file"
+    }
+
+    async fn spot_price(&self, asset: &str, currency: &str) -> Result<f64, WalletError> {
+        self.prices
+            .get(&(asset.to_string(), currency.to_string()))
+            .copied()
+            .ok_or_else(|| unavailable(asset, currency))
+    }
+
+    async fn historical_price(
+        &self,
+        asset: &str,
+        currency: &str,
+        _date: NaiveDate,
+    ) -> Result<f64, WalletError> {
+        self.spot_price(asset, currency).await
+    }
+
+    /// The file holds a single price per pair, whatever the day.
+    fn serves_history(&self) -> bool {
+        false
+    }
+}
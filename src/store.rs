@@ -0,0 +1,1261 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::NaiveDate;
+use rusqlite::{Connection, OptionalExtension, params};
+
+use primitive_types::U256;
+
+use crate::{
+    address::Address,
+    amount::Wei,
+    enums::{Direction, NftStandard},
+    errors::WalletError,
+    ethereum::chain::Chain,
+    structs::{NftTransfer, TokenTransfer, Transaction, WatchCursor},
+};
+
+/// Version of the cache layout. Bumping it drops and rebuilds the cache on next open.
+const SCHEMA_VERSION: i64 = 10;
+
+/// Tables owned by the store, dropped when the schema version changes.
+///
+/// Watch cursors are left out: they point into the history by block and
+/// hash, so they stay valid once the history is downloaded again.
+const TABLES: &[&str] = &[
+    "transactions",
+    "token_transfers",
+    "nft_transfers",
+    "sync_state",
+    "historical_prices",
+    "contract_flags",
+];
+
+/// First cache version whose watch cursors are history positions. Older
+/// caches stored row ids, which are dropped.
+const WATCH_CURSORS_VERSION: i64 = 8;
+
+/// First cache version with a watch cursor per history kind. The single
+/// cursor of older caches is copied to every kind.
+const WATCH_CURSOR_KINDS_VERSION: i64 = 10;
+
+/// Sync cursor of the normal transaction history.
+pub const TRANSACTIONS_KIND: &str = "txlist";
+
+/// Sync cursor of the internal transaction history.
+pub const INTERNAL_TRANSACTIONS_KIND: &str = "txlistinternal";
+
+/// Sync cursor of the ERC-20 token transfer history.
+pub const TOKEN_TRANSFERS_KIND: &str = "tokentx";
+
+/// Sync cursor of the ERC-721 transfer history.
+pub const ERC721_TRANSFERS_KIND: &str = "tokennfttx";
+
+/// Sync cursor of the ERC-1155 transfer history.
+pub const ERC1155_TRANSFERS_KIND: &str = "token1155tx";
+
+/// Local SQLite cache of parsed transactions, keyed by chain and address.
+///
+/// Alongside the transactions it records the last block synced for each
+/// address and history kind, so later syncs only ask the explorer for newer
+/// blocks, the daily fiat prices used to value past transactions, which
+/// counterparties are contracts, and how far watch mode has reported each
+/// address and history kind.
+pub struct TransactionStore {
+    conn: Mutex<Connection>,
+}
+
+impl TransactionStore {
+    /// Opens (or creates) the cache database at `path`.
+    ///
+    /// A cache written with an older layout is discarded and rebuilt, since
+    /// everything in it can be downloaded again.
+    pub fn open(path: &Path) -> Result<Self, WalletError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Opens an empty cache that lives only in memory.
+    #[cfg(test)]
+    pub(crate) fn open_in_memory() -> Result<Self, WalletError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    /// Brings the schema of `conn` up to date and wraps it in a store.
+    fn init(conn: Connection) -> Result<Self, WalletError> {
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            for table in TABLES {
+                conn.execute(&format!("DROP TABLE IF EXISTS {}", table), [])?;
+            }
+        }
+        let split_watch_cursors =
+            (WATCH_CURSORS_VERSION..WATCH_CURSOR_KINDS_VERSION).contains(&version);
+        if version < WATCH_CURSORS_VERSION {
+            conn.execute("DROP TABLE IF EXISTS watch_cursors", [])?;
+        } else if split_watch_cursors {
+            conn.execute(
+                "ALTER TABLE watch_cursors RENAME TO watch_cursors_single",
+                [],
+            )?;
+        }
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS transactions (
+                chain_id     INTEGER NOT NULL,
+                address      TEXT    NOT NULL,
+                hash         TEXT    NOT NULL,
+                block_number INTEGER NOT NULL,
+                timestamp    INTEGER NOT NULL,
+                tx_from      TEXT    NOT NULL,
+                tx_to        TEXT    NOT NULL,
+                gas_used     INTEGER NOT NULL,
+                gas_price    TEXT    NOT NULL,
+                quantity     TEXT    NOT NULL,
+                date         TEXT    NOT NULL,
+                internal     INTEGER NOT NULL,
+                trace_id     TEXT    NOT NULL,
+                is_error     INTEGER NOT NULL,
+                PRIMARY KEY (chain_id, address, hash, internal, trace_id)
+            );
+            CREATE TABLE IF NOT EXISTS token_transfers (
+                chain_id     INTEGER NOT NULL,
+                address      TEXT    NOT NULL,
+                hash         TEXT    NOT NULL,
+                log_index    INTEGER NOT NULL,
+                block_number INTEGER NOT NULL,
+                timestamp    INTEGER NOT NULL,
+                contract     TEXT    NOT NULL,
+                symbol       TEXT    NOT NULL,
+                decimals     INTEGER NOT NULL,
+                tx_from      TEXT    NOT NULL,
+                tx_to        TEXT    NOT NULL,
+                amount       TEXT    NOT NULL,
+                date         TEXT    NOT NULL,
+                PRIMARY KEY (chain_id, address, hash, log_index)
+            );
+            CREATE TABLE IF NOT EXISTS nft_transfers (
+                chain_id     INTEGER NOT NULL,
+                address      TEXT    NOT NULL,
+                hash         TEXT    NOT NULL,
+                log_index    INTEGER NOT NULL,
+                block_number INTEGER NOT NULL,
+                timestamp    INTEGER NOT NULL,
+                contract     TEXT    NOT NULL,
+                symbol       TEXT    NOT NULL,
+                standard     TEXT    NOT NULL,
+                token_id     TEXT    NOT NULL,
+                amount       TEXT    NOT NULL,
+                tx_from      TEXT    NOT NULL,
+                tx_to        TEXT    NOT NULL,
+                date         TEXT    NOT NULL,
+                PRIMARY KEY (chain_id, address, hash, log_index, contract, token_id, tx_from, tx_to)
+            );
+            CREATE TABLE IF NOT EXISTS sync_state (
+                chain_id   INTEGER NOT NULL,
+                address    TEXT    NOT NULL,
+                kind       TEXT    NOT NULL,
+                last_block INTEGER NOT NULL,
+                PRIMARY KEY (chain_id, address, kind)
+            );
+            CREATE TABLE IF NOT EXISTS historical_prices (
+                asset    TEXT NOT NULL,
+                currency TEXT NOT NULL,
+                date     TEXT NOT NULL,
+                price    REAL NOT NULL,
+                PRIMARY KEY (asset, currency, date)
+            );
+            CREATE TABLE IF NOT EXISTS contract_flags (
+                chain_id    INTEGER NOT NULL,
+                address     TEXT    NOT NULL,
+                is_contract INTEGER NOT NULL,
+                PRIMARY KEY (chain_id, address)
+            );
+            CREATE TABLE IF NOT EXISTS watch_cursors (
+                chain_id     INTEGER NOT NULL,
+                address      TEXT    NOT NULL,
+                kind         TEXT    NOT NULL,
+                block_number INTEGER NOT NULL,
+                internal     INTEGER NOT NULL,
+                hash         TEXT    NOT NULL,
+                trace_id     TEXT    NOT NULL,
+                PRIMARY KEY (chain_id, address, kind)
+            );",
+        )?;
+        if split_watch_cursors {
+            for kind in [TRANSACTIONS_KIND, INTERNAL_TRANSACTIONS_KIND] {
+                conn.execute(
+                    "INSERT INTO watch_cursors
+                        (chain_id, address, kind, block_number, internal, hash, trace_id)
+                     SELECT chain_id, address, ?1, block_number, internal, hash, trace_id
+                     FROM watch_cursors_single",
+                    params![kind],
+                )?;
+            }
+            conn.execute("DROP TABLE watch_cursors_single", [])?;
+        }
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+        Ok(TransactionStore {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Returns the last block synced for an address, or `None` if it was never synced.
+    ///
+    /// # Arguments
+    /// * `kind` - History kind, e.g. [`TRANSACTIONS_KIND`].
+    pub fn last_synced_block(
+        &self,
+        chain: Chain,
+        address: &Address,
+        kind: &str,
+    ) -> Result<Option<u64>, WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+
+        let last_block = conn
+            .query_row(
+                "SELECT last_block FROM sync_state
+                 WHERE chain_id = ?1 AND address = ?2 AND kind = ?3",
+                params![chain.chain_id() as i64, address.as_str(), kind],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+
+        Ok(last_block.map(|block| block as u64))
+    }
+
+    /// Returns the earliest block of any cached transaction or token transfer
+    /// of an address, or `None` if nothing is cached.
+    pub fn first_seen_block(
+        &self,
+        chain: Chain,
+        address: &Address,
+    ) -> Result<Option<u64>, WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+
+        let first_block = conn.query_row(
+            "SELECT MIN(block_number) FROM (
+                SELECT block_number FROM transactions WHERE chain_id = ?1 AND address = ?2
+                UNION ALL
+                SELECT block_number FROM token_transfers WHERE chain_id = ?1 AND address = ?2
+             )",
+            params![chain.chain_id() as i64, address.as_str()],
+            |row| row.get::<_, Option<i64>>(0),
+        )?;
+
+        Ok(first_block.map(|block| block as u64))
+    }
+
+    /// Stores newly fetched transactions and advances the sync cursor of `kind` to `last_block`.
+    ///
+    /// Transactions already in the cache are skipped, so overlapping syncs are harmless.
+    ///
+    /// # Arguments
+    /// * `kind` - History the transactions come from, [`TRANSACTIONS_KIND`]
+    ///   or [`INTERNAL_TRANSACTIONS_KIND`].
+    ///
+    /// # Returns
+    /// * The number of transactions that were not cached before.
+    pub fn save_transactions(
+        &self,
+        chain: Chain,
+        address: &Address,
+        transactions: &[Transaction],
+        kind: &str,
+        last_block: u64,
+    ) -> Result<usize, WalletError> {
+        let mut conn = self.conn.lock().expect("store lock poisoned");
+        let db_tx = conn.transaction()?;
+        let address = address.as_str();
+        let mut inserted = 0;
+
+        {
+            let mut insert = db_tx.prepare(
+                "INSERT OR IGNORE INTO transactions
+                    (chain_id, address, hash, block_number, timestamp,
+                     tx_from, tx_to, gas_used, gas_price, quantity, date, internal, trace_id,
+                     is_error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            )?;
+
+            for tx in transactions {
+                inserted += insert.execute(params![
+                    chain.chain_id() as i64,
+                    address,
+                    tx.hash,
+                    tx.block_number as i64,
+                    tx.timestamp as i64,
+                    tx.from,
+                    tx.to,
+                    tx.gas_used as i64,
+                    tx.gas_price.to_wei_string(),
+                    tx.quantity.to_wei_string(),
+                    tx.date,
+                    tx.internal,
+                    tx.trace_id,
+                    tx.is_error,
+                ])?;
+            }
+        }
+
+        set_last_block(&db_tx, chain, address, kind, last_block)?;
+        db_tx.commit()?;
+
+        Ok(inserted)
+    }
+
+    /// Loads cached transactions for an address, oldest first.
+    ///
+    /// Internal transactions are merged into the history, after the normal
+    /// transactions of the same block.
+    ///
+    /// # Arguments
+    /// * `limit` - Number of most recent transactions to return, or `None` for all of them.
+    pub fn load_transactions(
+        &self,
+        chain: Chain,
+        address: &Address,
+        limit: Option<usize>,
+    ) -> Result<Vec<Transaction>, WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+
+        let mut query = conn.prepare(
+            "SELECT hash, block_number, timestamp, tx_from, tx_to, gas_used, gas_price, quantity,
+                    date, internal, trace_id, is_error
+             FROM transactions
+             WHERE chain_id = ?1 AND address = ?2
+             ORDER BY block_number DESC, internal DESC, rowid DESC
+             LIMIT ?3",
+        )?;
+
+        let limit = limit.map_or(-1, |limit| limit as i64);
+        let rows = query.query_map(
+            params![chain.chain_id() as i64, address.as_str(), limit],
+            transaction_columns,
+        )?;
+
+        let mut transactions = Vec::new();
+        for row in rows {
+            transactions.push(build_transaction(chain, address, row?)?);
+        }
+
+        // Read newest first so the limit keeps the most recent ones.
+        transactions.reverse();
+        Ok(transactions)
+    }
+
+    /// Loads the transactions of one history kind cached for an address after
+    /// a watch cursor, in history order.
+    ///
+    /// The history is ordered by block, normal transactions before internal
+    /// ones, then by hash and trace, which does not depend on when a
+    /// transaction was cached.
+    ///
+    /// # Arguments
+    /// * `kind` - [`TRANSACTIONS_KIND`] or [`INTERNAL_TRANSACTIONS_KIND`].
+    /// * `after` - Position of the last transaction already seen; the default
+    ///   cursor returns all of them.
+    pub fn load_transactions_after(
+        &self,
+        chain: Chain,
+        address: &Address,
+        kind: &str,
+        after: &WatchCursor,
+    ) -> Result<Vec<Transaction>, WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+
+        let mut query = conn.prepare(
+            "SELECT hash, block_number, timestamp, tx_from, tx_to, gas_used, gas_price, quantity,
+                    date, internal, trace_id, is_error
+             FROM transactions
+             WHERE chain_id = ?1 AND address = ?2 AND internal = ?3
+               AND (block_number, internal, hash, trace_id) > (?4, ?5, ?6, ?7)
+             ORDER BY block_number, internal, hash, trace_id",
+        )?;
+
+        let rows = query.query_map(
+            params![
+                chain.chain_id() as i64,
+                address.as_str(),
+                is_internal_kind(kind),
+                after.block_number as i64,
+                after.internal,
+                after.hash,
+                after.trace_id,
+            ],
+            transaction_columns,
+        )?;
+
+        let mut transactions = Vec::new();
+        for row in rows {
+            transactions.push(build_transaction(chain, address, row?)?);
+        }
+
+        Ok(transactions)
+    }
+
+    /// Returns the position of the last transaction of one history kind
+    /// cached for an address, or the default cursor if there is none.
+    ///
+    /// # Arguments
+    /// * `kind` - [`TRANSACTIONS_KIND`] or [`INTERNAL_TRANSACTIONS_KIND`].
+    pub fn last_transaction_cursor(
+        &self,
+        chain: Chain,
+        address: &Address,
+        kind: &str,
+    ) -> Result<WatchCursor, WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+
+        let cursor = conn
+            .query_row(
+                "SELECT block_number, internal, hash, trace_id FROM transactions
+                 WHERE chain_id = ?1 AND address = ?2 AND internal = ?3
+                 ORDER BY block_number DESC, internal DESC, hash DESC, trace_id DESC
+                 LIMIT 1",
+                params![
+                    chain.chain_id() as i64,
+                    address.as_str(),
+                    is_internal_kind(kind)
+                ],
+                watch_cursor,
+            )
+            .optional()?;
+
+        Ok(cursor.unwrap_or_default())
+    }
+
+    /// Returns the position of the last transaction of one history kind watch
+    /// mode reported for an address, or `None` if it was never watched.
+    ///
+    /// # Arguments
+    /// * `kind` - [`TRANSACTIONS_KIND`] or [`INTERNAL_TRANSACTIONS_KIND`].
+    pub fn load_watch_cursor(
+        &self,
+        chain: Chain,
+        address: &Address,
+        kind: &str,
+    ) -> Result<Option<WatchCursor>, WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+
+        let cursor = conn
+            .query_row(
+                "SELECT block_number, internal, hash, trace_id FROM watch_cursors
+                 WHERE chain_id = ?1 AND address = ?2 AND kind = ?3",
+                params![chain.chain_id() as i64, address.as_str(), kind],
+                watch_cursor,
+            )
+            .optional()?;
+
+        Ok(cursor)
+    }
+
+    /// Records the position of the last transaction of one history kind watch
+    /// mode reported for an address.
+    ///
+    /// # Arguments
+    /// * `kind` - [`TRANSACTIONS_KIND`] or [`INTERNAL_TRANSACTIONS_KIND`].
+    pub fn save_watch_cursor(
+        &self,
+        chain: Chain,
+        address: &Address,
+        kind: &str,
+        cursor: &WatchCursor,
+    ) -> Result<(), WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+
+        conn.execute(
+            "INSERT INTO watch_cursors
+                (chain_id, address, kind, block_number, internal, hash, trace_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (chain_id, address, kind) DO UPDATE SET
+                block_number = excluded.block_number,
+                internal = excluded.internal,
+                hash = excluded.hash,
+                trace_id = excluded.trace_id",
+            params![
+                chain.chain_id() as i64,
+                address.as_str(),
+                kind,
+                cursor.block_number as i64,
+                cursor.internal,
+                cursor.hash,
+                cursor.trace_id,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Stores newly fetched token transfers and advances their sync cursor to `last_block`.
+    ///
+    /// # Returns
+    /// * The number of token transfers that were not cached before.
+    pub fn save_token_transfers(
+        &self,
+        chain: Chain,
+        address: &Address,
+        transfers: &[TokenTransfer],
+        last_block: u64,
+    ) -> Result<usize, WalletError> {
+        let mut conn = self.conn.lock().expect("store lock poisoned");
+        let db_tx = conn.transaction()?;
+        let address = address.as_str();
+        let mut inserted = 0;
+
+        {
+            let mut insert = db_tx.prepare(
+                "INSERT OR IGNORE INTO token_transfers
+                    (chain_id, address, hash, log_index, block_number, timestamp,
+                     contract, symbol, decimals, tx_from, tx_to, amount, date)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            )?;
+
+            for transfer in transfers {
+                inserted += insert.execute(params![
+                    chain.chain_id() as i64,
+                    address,
+                    transfer.hash,
+                    transfer.log_index as i64,
+                    transfer.block_number as i64,
+                    transfer.timestamp as i64,
+                    transfer.contract,
+                    transfer.symbol,
+                    transfer.decimals,
+                    transfer.from,
+                    transfer.to,
+                    transfer.amount.to_string(),
+                    transfer.date,
+                ])?;
+            }
+        }
+
+        set_last_block(&db_tx, chain, address, TOKEN_TRANSFERS_KIND, last_block)?;
+        db_tx.commit()?;
+
+        Ok(inserted)
+    }
+
+    /// Loads cached token transfers for an address, oldest first.
+    ///
+    /// # Arguments
+    /// * `limit` - Number of most recent transfers to return, or `None` for all of them.
+    pub fn load_token_transfers(
+        &self,
+        chain: Chain,
+        address: &Address,
+        limit: Option<usize>,
+    ) -> Result<Vec<TokenTransfer>, WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+        let address = address.as_str();
+
+        let mut query = conn.prepare(
+            "SELECT hash, log_index, block_number, timestamp, contract, symbol, decimals,
+                    tx_from, tx_to, amount, date
+             FROM token_transfers
+             WHERE chain_id = ?1 AND address = ?2
+             ORDER BY block_number DESC, log_index DESC
+             LIMIT ?3",
+        )?;
+
+        let limit = limit.map_or(-1, |limit| limit as i64);
+        let rows = query.query_map(params![chain.chain_id() as i64, address, limit], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, u8>(6)?,
+                row.get::<_, String>(7)?,
+                row.get::<_, String>(8)?,
+                row.get::<_, String>(9)?,
+                row.get::<_, String>(10)?,
+            ))
+        })?;
+
+        let mut transfers = Vec::new();
+        for row in rows {
+            let (
+                hash,
+                log_index,
+                block_number,
+                timestamp,
+                contract,
+                symbol,
+                decimals,
+                from,
+                to,
+                amount,
+                date,
+            ) = row?;
+            let direction = Direction::of(address, &from, &to);
+            transfers.push(TokenTransfer {
+                chain,
+                hash,
+                log_index: log_index as u64,
+                block_number: block_number as u64,
+                timestamp: timestamp as u64,
+                contract,
+                symbol,
+                decimals,
+                from,
+                to,
+                amount: U256::from_dec_str(&amount)
+                    .map_err(|_| WalletError::AmountParse { value: amount })?,
+                direction,
+                date,
+            });
+        }
+
+        // Read newest first so the limit keeps the most recent ones.
+        transfers.reverse();
+        Ok(transfers)
+    }
+
+    /// Stores newly fetched NFT transfers and advances the `kind` sync cursor to `last_block`.
+    ///
+    /// # Returns
+    /// * The number of NFT transfers that were not cached before.
+    pub fn save_nft_transfers(
+        &self,
+        chain: Chain,
+        address: &Address,
+        transfers: &[NftTransfer],
+        kind: &str,
+        last_block: u64,
+    ) -> Result<usize, WalletError> {
+        let mut conn = self.conn.lock().expect("store lock poisoned");
+        let db_tx = conn.transaction()?;
+        let address = address.as_str();
+        let mut inserted = 0;
+
+        {
+            let mut insert = db_tx.prepare(
+                "INSERT OR IGNORE INTO nft_transfers
+                    (chain_id, address, hash, log_index, block_number, timestamp, contract,
+                     symbol, standard, token_id, amount, tx_from, tx_to, date)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            )?;
+
+            for transfer in transfers {
+                inserted += insert.execute(params![
+                    chain.chain_id() as i64,
+                    address,
+                    transfer.hash,
+                    transfer.log_index as i64,
+                    transfer.block_number as i64,
+                    transfer.timestamp as i64,
+                    transfer.contract,
+                    transfer.symbol,
+                    transfer.standard.to_string(),
+                    transfer.token_id.to_string(),
+                    transfer.amount.to_string(),
+                    transfer.from.to_lowercase(),
+                    transfer.to.to_lowercase(),
+                    transfer.date,
+                ])?;
+            }
+        }
+
+        set_last_block(&db_tx, chain, address, kind, last_block)?;
+        db_tx.commit()?;
+
+        Ok(inserted)
+    }
+
+    /// Loads cached NFT transfers for an address, oldest first.
+    pub fn load_nft_transfers(
+        &self,
+        chain: Chain,
+        address: &Address,
+    ) -> Result<Vec<NftTransfer>, WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+        let address = address.as_str();
+
+        let mut query = conn.prepare(
+            "SELECT hash, block_number, timestamp, contract, symbol, standard, token_id,
+                    amount, tx_from, tx_to, date, log_index
+             FROM nft_transfers
+             WHERE chain_id = ?1 AND address = ?2
+             ORDER BY block_number, log_index, rowid",
+        )?;
+
+        let rows = query.query_map(params![chain.chain_id() as i64, address], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(7)?,
+                row.get::<_, String>(8)?,
+                row.get::<_, String>(9)?,
+                row.get::<_, String>(10)?,
+                row.get::<_, i64>(11)?,
+            ))
+        })?;
+
+        let mut transfers = Vec::new();
+        for row in rows {
+            let (
+                hash,
+                block_number,
+                timestamp,
+                contract,
+                symbol,
+                standard,
+                token_id,
+                amount,
+                from,
+                to,
+                date,
+                log_index,
+            ) = row?;
+            let direction = Direction::of(address, &from, &to);
+            let standard = NftStandard::from_label(&standard).ok_or(WalletError::Missing {
+                field: "standard".to_string(),
+            })?;
+            transfers.push(NftTransfer {
+                chain,
+                hash,
+                block_number: block_number as u64,
+                log_index: log_index as u64,
+                timestamp: timestamp as u64,
+                contract,
+                symbol,
+                standard,
+                token_id: U256::from_dec_str(&token_id)
+                    .map_err(|_| WalletError::AmountParse { value: token_id })?,
+                amount: U256::from_dec_str(&amount)
+                    .map_err(|_| WalletError::AmountParse { value: amount })?,
+                from,
+                to,
+                direction,
+                date,
+            });
+        }
+
+        Ok(transfers)
+    }
+
+    /// Returns the cached daily price of `asset` in `currency`, if any.
+    pub fn load_price(
+        &self,
+        asset: &str,
+        currency: &str,
+        date: NaiveDate,
+    ) -> Result<Option<f64>, WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+        Ok(conn
+            .query_row(
+                "SELECT price FROM historical_prices
+                 WHERE asset = ?1 AND currency = ?2 AND date = ?3",
+                params![asset, currency, date.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Caches the daily price of `asset` in `currency`.
+    pub fn save_price(
+        &self,
+        asset: &str,
+        currency: &str,
+        date: NaiveDate,
+        price: f64,
+    ) -> Result<(), WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+        conn.execute(
+            "INSERT OR REPLACE INTO historical_prices (asset, currency, date, price)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![asset, currency, date.to_string(), price],
+        )?;
+        Ok(())
+    }
+
+    /// Returns whether an address is known to hold contract code, if it was checked before.
+    pub fn load_is_contract(
+        &self,
+        chain: Chain,
+        address: &str,
+    ) -> Result<Option<bool>, WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+        Ok(conn
+            .query_row(
+                "SELECT is_contract FROM contract_flags WHERE chain_id = ?1 AND address = ?2",
+                params![chain.chain_id() as i64, address.to_lowercase()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Caches whether an address holds contract code.
+    pub fn save_is_contract(
+        &self,
+        chain: Chain,
+        address: &str,
+        is_contract: bool,
+    ) -> Result<(), WalletError> {
+        let conn = self.conn.lock().expect("store lock poisoned");
+        conn.execute(
+            "INSERT OR REPLACE INTO contract_flags (chain_id, address, is_contract)
+             VALUES (?1, ?2, ?3)",
+            params![chain.chain_id() as i64, address.to_lowercase(), is_contract],
+        )?;
+        Ok(())
+    }
+}
+
+/// Advances the sync cursor of one history kind inside an open database transaction.
+fn set_last_block(
+    db_tx: &rusqlite::Transaction<'_>,
+    chain: Chain,
+    address: &str,
+    kind: &str,
+    last_block: u64,
+) -> Result<(), WalletError> {
+    db_tx.execute(
+        "INSERT INTO sync_state (chain_id, address, kind, last_block) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (chain_id, address, kind) DO UPDATE SET last_block = excluded.last_block",
+        params![chain.chain_id() as i64, address, kind, last_block as i64],
+    )?;
+    Ok(())
+}
+
+/// Columns of a cached transaction, in the order they are selected.
+type TransactionColumns = (
+    String,
+    i64,
+    i64,
+    String,
+    String,
+    i64,
+    String,
+    String,
+    String,
+    bool,
+    String,
+    bool,
+);
+
+/// Reads the transaction columns of a row.
+fn transaction_columns(row: &rusqlite::Row<'_>) -> rusqlite::Result<TransactionColumns> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+        row.get(7)?,
+        row.get(8)?,
+        row.get(9)?,
+        row.get(10)?,
+        row.get(11)?,
+    ))
+}
+
+/// Whether the transactions of a history kind are internal ones.
+fn is_internal_kind(kind: &str) -> bool {
+    kind == INTERNAL_TRANSACTIONS_KIND
+}
+
+/// Reads a watch cursor from its block, internal, hash and trace columns.
+fn watch_cursor(row: &rusqlite::Row<'_>) -> rusqlite::Result<WatchCursor> {
+    Ok(WatchCursor {
+        block_number: row.get::<_, i64>(0)? as u64,
+        internal: row.get(1)?,
+        hash: row.get(2)?,
+        trace_id: row.get(3)?,
+    })
+}
+
+/// Builds a [`Transaction`] of `address` from its cached columns.
+fn build_transaction(
+    chain: Chain,
+    address: &Address,
+    columns: TransactionColumns,
+) -> Result<Transaction, WalletError> {
+    let (
+        hash,
+        block_number,
+        timestamp,
+        from,
+        to,
+        gas_used,
+        gas_price,
+        quantity,
+        date,
+        internal,
+        trace_id,
+        is_error,
+    ) = columns;
+
+    Ok(Transaction {
+        chain,
+        hash,
+        block_number: block_number as u64,
+        timestamp: timestamp as u64,
+        direction: Direction::of(address.as_str(), &from, &to),
+        from,
+        to,
+        quantity: Wei::from_dec_str(&quantity)?,
+        gas_used: gas_used as u64,
+        gas_price: Wei::from_dec_str(&gas_price)?,
+        date,
+        internal,
+        is_error,
+        trace_id,
+        fiat_values: Vec::new(),
+        fee_fiat_values: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wallet() -> Address {
+        Address::parse(Transaction::SAMPLE_WALLET).unwrap()
+    }
+
+    fn transfer(block_number: u64, log_index: u64) -> TokenTransfer {
+        TokenTransfer {
+            chain: Chain::Ethereum,
+            hash: format!("0x{:064x}", block_number),
+            log_index,
+            block_number,
+            timestamp: block_number * 12,
+            contract: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".to_string(),
+            symbol: "USDC".to_string(),
+            decimals: 6,
+            from: "0x00000000000000000000000000000000000000bb".to_string(),
+            to: Transaction::SAMPLE_WALLET.to_string(),
+            amount: U256::from(1_000_000),
+            direction: Direction::In,
+            date: String::new(),
+        }
+    }
+
+    /// Builds an internal transfer to the wallet made by the transaction of `timestamp`.
+    fn internal(milli: u64, timestamp: u64, trace_id: &str) -> Transaction {
+        let mut tx = Transaction::received(milli, timestamp);
+        tx.internal = true;
+        tx.trace_id = trace_id.to_string();
+        tx
+    }
+
+    fn cursors(transactions: &[Transaction]) -> Vec<WatchCursor> {
+        transactions.iter().map(WatchCursor::of).collect()
+    }
+
+    #[test]
+    fn sync_cursors_are_kept_per_kind() {
+        let store = TransactionStore::open_in_memory().unwrap();
+        let wallet = wallet();
+        let last_block = |kind| {
+            store
+                .last_synced_block(Chain::Ethereum, &wallet, kind)
+                .unwrap()
+        };
+        assert_eq!(last_block(TRANSACTIONS_KIND), None);
+
+        let txs = [Transaction::received(100, 1200)];
+        store
+            .save_transactions(Chain::Ethereum, &wallet, &txs, TRANSACTIONS_KIND, 150)
+            .unwrap();
+        store
+            .save_transactions(
+                Chain::Ethereum,
+                &wallet,
+                &[],
+                INTERNAL_TRANSACTIONS_KIND,
+                120,
+            )
+            .unwrap();
+        assert_eq!(last_block(TRANSACTIONS_KIND), Some(150));
+        assert_eq!(last_block(INTERNAL_TRANSACTIONS_KIND), Some(120));
+        assert_eq!(last_block(TOKEN_TRANSFERS_KIND), None);
+
+        // Saving the same transactions again only moves the cursor.
+        let inserted = store
+            .save_transactions(Chain::Ethereum, &wallet, &txs, TRANSACTIONS_KIND, 200)
+            .unwrap();
+        assert_eq!(inserted, 0);
+        assert_eq!(last_block(TRANSACTIONS_KIND), Some(200));
+
+        let other = Address::parse("0x00000000000000000000000000000000000000cc").unwrap();
+        assert_eq!(
+            store
+                .last_synced_block(Chain::Ethereum, &other, TRANSACTIONS_KIND)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn empty_wallet_has_the_default_cursor() {
+        let store = TransactionStore::open_in_memory().unwrap();
+
+        for kind in [TRANSACTIONS_KIND, INTERNAL_TRANSACTIONS_KIND] {
+            assert_eq!(
+                store
+                    .last_transaction_cursor(Chain::Ethereum, &wallet(), kind)
+                    .unwrap(),
+                WatchCursor::default()
+            );
+            assert_eq!(
+                store
+                    .load_watch_cursor(Chain::Ethereum, &wallet(), kind)
+                    .unwrap(),
+                None
+            );
+            assert!(
+                store
+                    .load_transactions_after(
+                        Chain::Ethereum,
+                        &wallet(),
+                        kind,
+                        &WatchCursor::default()
+                    )
+                    .unwrap()
+                    .is_empty()
+            );
+        }
+    }
+
+    #[test]
+    fn transactions_after_a_cursor_follow_history_order() {
+        let store = TransactionStore::open_in_memory().unwrap();
+        let wallet = wallet();
+        store
+            .save_transactions(
+                Chain::Ethereum,
+                &wallet,
+                &[
+                    Transaction::received(100, 1320),
+                    Transaction::received(200, 1200),
+                ],
+                TRANSACTIONS_KIND,
+                110,
+            )
+            .unwrap();
+        store
+            .save_transactions(
+                Chain::Ethereum,
+                &wallet,
+                &[internal(50, 1200, "0_1"), internal(60, 1200, "0")],
+                INTERNAL_TRANSACTIONS_KIND,
+                100,
+            )
+            .unwrap();
+
+        let normal = store
+            .load_transactions_after(
+                Chain::Ethereum,
+                &wallet,
+                TRANSACTIONS_KIND,
+                &WatchCursor::default(),
+            )
+            .unwrap();
+        let blocks: Vec<_> = normal
+            .iter()
+            .map(|tx| (tx.block_number, tx.internal))
+            .collect();
+        assert_eq!(blocks, [(100, false), (110, false)]);
+        assert_eq!(
+            store
+                .last_transaction_cursor(Chain::Ethereum, &wallet, TRANSACTIONS_KIND)
+                .unwrap(),
+            WatchCursor::of(&normal[1])
+        );
+
+        let internal = store
+            .load_transactions_after(
+                Chain::Ethereum,
+                &wallet,
+                INTERNAL_TRANSACTIONS_KIND,
+                &WatchCursor::default(),
+            )
+            .unwrap();
+        let traces: Vec<_> = internal.iter().map(|tx| tx.trace_id.as_str()).collect();
+        assert_eq!(traces, ["0", "0_1"]);
+        assert_eq!(
+            store
+                .last_transaction_cursor(Chain::Ethereum, &wallet, INTERNAL_TRANSACTIONS_KIND)
+                .unwrap(),
+            WatchCursor::of(&internal[1])
+        );
+
+        // Resuming after the first internal transfer returns the rest, in order.
+        let rest = store
+            .load_transactions_after(
+                Chain::Ethereum,
+                &wallet,
+                INTERNAL_TRANSACTIONS_KIND,
+                &WatchCursor::of(&internal[0]),
+            )
+            .unwrap();
+        assert_eq!(cursors(&rest), cursors(&internal[1..]));
+
+        let none = store
+            .load_transactions_after(
+                Chain::Ethereum,
+                &wallet,
+                TRANSACTIONS_KIND,
+                &WatchCursor::of(&normal[1]),
+            )
+            .unwrap();
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn late_internal_transaction_is_after_its_own_cursor() {
+        let store = TransactionStore::open_in_memory().unwrap();
+        let wallet = wallet();
+        let reported = Transaction::received(100, 1320);
+        store
+            .save_transactions(
+                Chain::Ethereum,
+                &wallet,
+                std::slice::from_ref(&reported),
+                TRANSACTIONS_KIND,
+                110,
+            )
+            .unwrap();
+        let internal_cursor = store
+            .last_transaction_cursor(Chain::Ethereum, &wallet, INTERNAL_TRANSACTIONS_KIND)
+            .unwrap();
+
+        // Indexed after the transaction of block 110 was reported.
+        store
+            .save_transactions(
+                Chain::Ethereum,
+                &wallet,
+                &[internal(50, 1200, "0")],
+                INTERNAL_TRANSACTIONS_KIND,
+                100,
+            )
+            .unwrap();
+
+        let late = store
+            .load_transactions_after(
+                Chain::Ethereum,
+                &wallet,
+                INTERNAL_TRANSACTIONS_KIND,
+                &internal_cursor,
+            )
+            .unwrap();
+        assert_eq!(late.len(), 1);
+        assert_eq!(late[0].block_number, 100);
+        assert!(WatchCursor::of(&late[0]) < WatchCursor::of(&reported));
+    }
+
+    #[test]
+    fn watch_cursors_round_trip_per_kind() {
+        let store = TransactionStore::open_in_memory().unwrap();
+        let normal = WatchCursor::of(&Transaction::received(100, 1320));
+        let first = WatchCursor::of(&internal(50, 1200, "0"));
+        let second = WatchCursor::of(&internal(50, 1200, "0_1"));
+
+        store
+            .save_watch_cursor(Chain::Ethereum, &wallet(), TRANSACTIONS_KIND, &normal)
+            .unwrap();
+        store
+            .save_watch_cursor(
+                Chain::Ethereum,
+                &wallet(),
+                INTERNAL_TRANSACTIONS_KIND,
+                &first,
+            )
+            .unwrap();
+        store
+            .save_watch_cursor(
+                Chain::Ethereum,
+                &wallet(),
+                INTERNAL_TRANSACTIONS_KIND,
+                &second,
+            )
+            .unwrap();
+
+        let load = |kind| {
+            store
+                .load_watch_cursor(Chain::Ethereum, &wallet(), kind)
+                .unwrap()
+        };
+        assert_eq!(load(TRANSACTIONS_KIND), Some(normal));
+        assert_eq!(load(INTERNAL_TRANSACTIONS_KIND), Some(second));
+    }
+
+    #[test]
+    fn single_watch_cursor_is_copied_to_every_kind() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE watch_cursors (
+                chain_id     INTEGER NOT NULL,
+                address      TEXT    NOT NULL,
+                block_number INTEGER NOT NULL,
+                internal     INTEGER NOT NULL,
+                hash         TEXT    NOT NULL,
+                trace_id     TEXT    NOT NULL,
+                PRIMARY KEY (chain_id, address)
+            );
+            INSERT INTO watch_cursors
+                VALUES (1, '0x00000000000000000000000000000000000000aa', 110, 0, '0x01', '');
+            PRAGMA user_version = 9;",
+        )
+        .unwrap();
+
+        let store = TransactionStore::init(conn).unwrap();
+
+        let expected = WatchCursor {
+            block_number: 110,
+            internal: false,
+            hash: "0x01".to_string(),
+            trace_id: String::new(),
+        };
+        for kind in [TRANSACTIONS_KIND, INTERNAL_TRANSACTIONS_KIND] {
+            assert_eq!(
+                store
+                    .load_watch_cursor(Chain::Ethereum, &wallet(), kind)
+                    .unwrap(),
+                Some(expected.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn token_transfer_limit_keeps_the_most_recent() {
+        let store = TransactionStore::open_in_memory().unwrap();
+        let transfers = [
+            transfer(10, 0),
+            transfer(20, 1),
+            transfer(20, 0),
+            transfer(30, 0),
+        ];
+        store
+            .save_token_transfers(Chain::Ethereum, &wallet(), &transfers, 30)
+            .unwrap();
+
+        let loaded = store
+            .load_token_transfers(Chain::Ethereum, &wallet(), Some(2))
+            .unwrap();
+        let positions: Vec<_> = loaded
+            .iter()
+            .map(|t| (t.block_number, t.log_index))
+            .collect();
+        assert_eq!(positions, [(20, 1), (30, 0)]);
+
+        let all = store
+            .load_token_transfers(Chain::Ethereum, &wallet(), None)
+            .unwrap();
+        let positions: Vec<_> = all.iter().map(|t| (t.block_number, t.log_index)).collect();
+        assert_eq!(positions, [(10, 0), (20, 0), (20, 1), (30, 0)]);
+        assert!(all.iter().all(|t| t.direction == Direction::In));
+    }
+}
@@ -1,84 +1,650 @@
-
+use std::collections::HashMap;
 use std::fmt;
 
+use chrono::NaiveDate;
+use primitive_types::U256;
+
+use crate::{
+    address::Address,
+    amount::{Wei, format_ether_delta, format_units},
+    currency::FiatAmount,
+    enums::{Direction, NftStandard},
+    ethereum::chain::Chain,
+};
+
 /// Represents a single Ethereum transaction.
 #[derive(Debug)]
 pub struct Transaction {
+    /// Chain the transaction was made on.
+    pub chain: Chain,
+    /// Transaction hash.
+    pub hash: String,
+    /// Number of the block the transaction was included in.
+    pub block_number: u64,
+    /// Unix timestamp of the block.
+    pub timestamp: u64,
     /// Sender wallet address.
     pub from: String,
     /// Recipient wallet address.
     pub to: String,
-    /// Value transferred in ETH.
-    pub quantity: f64,
-    /// Gas used for the transaction (in wei).
-    pub gas: String,
+    /// Value transferred, exact in wei.
+    pub quantity: Wei,
+    /// Whether the tracked wallet sent or received the value.
+    pub direction: Direction,
+    /// Units of gas consumed. Zero for internal transactions, whose gas is paid by the parent.
+    pub gas_used: u64,
+    /// Effective price paid per unit of gas. Zero for internal transactions.
+    pub gas_price: Wei,
     /// Date of the transaction (as a string).
     pub date: String,
+    /// Whether this is an internal transfer made by a contract call inside the transaction.
+    pub internal: bool,
+    /// Whether the transaction reverted. A failed transaction moved no value
+    /// but its sender still paid the fee.
+    pub is_error: bool,
+    /// Position of an internal transfer in the call tree, e.g. `0_1`. Empty for normal transactions.
+    pub trace_id: String,
+    /// Value in each quote currency at the time of the transaction. Empty until valued.
+    pub fiat_values: Vec<FiatAmount>,
+    /// Fee in each quote currency at the time of the transaction. Empty until valued.
+    pub fee_fiat_values: Vec<FiatAmount>,
 }
 
 impl fmt::Display for Transaction {
     /// Formats the transaction details in a human-readable multiline format.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format_detailed(&self.from, &self.to))
+    }
+}
+
+#[cfg(test)]
+impl Transaction {
+    /// Wallet the sample transactions belong to.
+    pub(crate) const SAMPLE_WALLET: &'static str = "0x00000000000000000000000000000000000000aa";
+
+    /// Builds a successful Ethereum transaction of [`Transaction::SAMPLE_WALLET`]
+    /// moving `quantity` in `direction`, without fee or fiat value.
+    pub(crate) fn sample(direction: Direction, quantity: Wei, timestamp: u64) -> Transaction {
+        let other = "0x00000000000000000000000000000000000000bb";
+        let (from, to) = match direction {
+            Direction::In => (other, Transaction::SAMPLE_WALLET),
+            Direction::Out => (Transaction::SAMPLE_WALLET, other),
+            Direction::SelfTransfer => (Transaction::SAMPLE_WALLET, Transaction::SAMPLE_WALLET),
+            Direction::ContractCreation => (Transaction::SAMPLE_WALLET, ""),
+            Direction::Unrelated => (other, other),
+        };
+
+        Transaction {
+            chain: Chain::Ethereum,
+            hash: format!("0x{:064x}", timestamp),
+            block_number: timestamp / 12,
+            timestamp,
+            from: from.to_string(),
+            to: to.to_string(),
+            quantity,
+            direction,
+            gas_used: 0,
+            gas_price: Wei::ZERO,
+            date: crate::date_utils::epoch_converter(timestamp.to_string()),
+            internal: false,
+            is_error: false,
+            trace_id: String::new(),
+            fiat_values: Vec::new(),
+            fee_fiat_values: Vec::new(),
+        }
+    }
+
+    /// Builds a transaction of `milli` thousandths of Ether received by the wallet.
+    pub(crate) fn received(milli: u64, timestamp: u64) -> Transaction {
+        Transaction::sample(Direction::In, Wei::milliether(milli), timestamp)
+    }
+
+    /// Builds a transaction sent by the wallet moving `milli` thousandths of
+    /// Ether and paying a fee of `fee_milli` thousandths of Ether.
+    pub(crate) fn sent(
+        direction: Direction,
+        milli: u64,
+        fee_milli: u64,
+        timestamp: u64,
+    ) -> Transaction {
+        let mut tx = Transaction::sample(direction, Wei::milliether(milli), timestamp);
+        tx.gas_used = fee_milli;
+        tx.gas_price = Wei::milliether(1);
+        tx
+    }
+}
+
+impl Transaction {
+    /// Returns the fee paid by the sender, gas used times gas price.
+    pub fn fee(&self) -> Wei {
+        Wei(self.gas_price.0 * U256::from(self.gas_used))
+    }
+
+    /// Formats the multiline view of the transaction with the given sender and recipient labels.
+    fn format_detailed(&self, from: &str, to: &str) -> String {
+        let symbol = self.chain.native_symbol();
+        let mut detailed = format!(
+            "{} ({}) on {}\n\
+             From: {}\n\
+             To: {}\n\
+             Value: {} {}{}\n",
+            match (self.internal, self.is_error) {
+                (true, _) => "Internal transaction",
+                (false, true) => "Failed transaction",
+                (false, false) => "Transaction",
+            },
+            self.direction,
+            self.date,
+            from,
+            match self.direction {
+                Direction::ContractCreation if to.is_empty() => "contract creation",
+                _ => to,
+            },
+            self.quantity.to_ether_string(),
+            symbol,
+            format_fiat_values(&self.fiat_values),
+        );
+
+        // Internal transfers pay no gas of their own.
+        if !self.internal {
+            detailed.push_str(&format!(
+                "Gas used: {}\n\
+                 Gas price: {} gwei\n\
+                 Fee: {} {}{}\n",
+                self.gas_used,
+                self.gas_price.to_gwei_string(),
+                self.fee().to_ether_string(),
+                symbol,
+                format_fiat_values(&self.fee_fiat_values),
+            ));
+        }
+
+        detailed
+    }
+
+    /// Prints a detailed view of the transaction.
+    ///
+    /// # Arguments
+    /// * `names` - Primary ENS names keyed by lowercase address, shown next to the addresses.
+    pub fn display_detailed(&self, names: &HashMap<String, String>) {
+        let from = match names.get(&self.from.to_lowercase()) {
+            Some(name) => format!("{} ({})", name, self.from),
+            None => self.from.clone(),
+        };
+        let to = match names.get(&self.to.to_lowercase()) {
+            Some(name) => format!("{} ({})", name, self.to),
+            None => self.to.clone(),
+        };
+        println!("{}", self.format_detailed(&from, &to));
+    }
+
+    /// Prints a compact, one-line summary of the transaction.
+    ///
+    /// Addresses are shortened, or replaced by their primary ENS name from `names`.
+    pub fn display_compact(&self, names: &HashMap<String, String>) {
+        let short = |address: &str| match names.get(&address.to_lowercase()) {
+            Some(name) => name.clone(),
+            None => address.get(..8).unwrap_or(address).to_string(),
+        };
+        println!(
+            "{}{} -> {} | {} {} ({})",
+            if self.internal { "[internal] " } else { "" },
+            short(&self.from),
+            short(&self.to),
+            self.quantity.to_ether_string(),
+            self.chain.native_symbol(),
+            self.direction
+        );
+    }
+}
+
+/// Formats fiat values as ` (€1.00, CHF 0.90)`, or an empty string when there are none.
+fn format_fiat_values(values: &[FiatAmount]) -> String {
+    if values.is_empty() {
+        return String::new();
+    }
+
+    format!(
+        " ({})",
+        values
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// Position of a transaction in the history of a wallet, up to which watch
+/// mode has reported.
+///
+/// The history is ordered by block, normal transactions before internal
+/// ones, then by hash and trace, so a cursor stays valid when the cache is
+/// rebuilt. The default cursor lies before every transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WatchCursor {
+    /// Block of the transaction.
+    pub block_number: u64,
+    /// Whether the transaction is internal.
+    pub internal: bool,
+    /// Hash of the transaction.
+    pub hash: String,
+    /// Position of an internal transfer in the call tree, empty for normal transactions.
+    pub trace_id: String,
+}
+
+impl WatchCursor {
+    /// Returns the position of `tx` in the history.
+    pub fn of(tx: &Transaction) -> WatchCursor {
+        WatchCursor {
+            block_number: tx.block_number,
+            internal: tx.internal,
+            hash: tx.hash.clone(),
+            trace_id: tx.trace_id.clone(),
+        }
+    }
+}
+
+/// A single ERC-20 token transfer involving the tracked wallet.
+#[derive(Debug)]
+pub struct TokenTransfer {
+    /// Chain the transfer was made on.
+    pub chain: Chain,
+    /// Hash of the transaction that emitted the transfer.
+    pub hash: String,
+    /// Position of the `Transfer` event in the block.
+    pub log_index: u64,
+    /// Number of the block the transfer was included in.
+    pub block_number: u64,
+    /// Unix timestamp of the block.
+    pub timestamp: u64,
+    /// Token contract address, lowercase.
+    pub contract: String,
+    /// Token ticker.
+    pub symbol: String,
+    /// Number of decimals of the token.
+    pub decimals: u8,
+    /// Sender address.
+    pub from: String,
+    /// Recipient address.
+    pub to: String,
+    /// Raw amount in the token's smallest unit.
+    pub amount: U256,
+    /// Whether the tracked wallet sent or received the tokens.
+    pub direction: Direction,
+    /// Date of the transfer (as a string).
+    pub date: String,
+}
+
+impl fmt::Display for TokenTransfer {
+    /// Formats the token transfer details in a human-readable multiline format.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Transaction on {}\n\
+            "Token transfer ({}) on {}\n\
              From: {}\n\
              To: {}\n\
-             Value: {} ETH\n\
-             Gas: {} wei\n",
-            self.date, self.from, self.to, self.quantity, self.gas,
+             Amount: {} {}\n\
+             Token: {}\n",
+            self.direction,
+            self.date,
+            self.from,
+            self.to,
+            self.formatted_amount(),
+            self.symbol,
+            self.contract,
         )
     }
 }
 
-impl Transaction {
-    /// Prints a detailed view of the transaction using `Display`.
+impl TokenTransfer {
+    /// Returns the amount formatted with the token's decimals.
+    pub fn formatted_amount(&self) -> String {
+        format_units(self.amount, self.decimals)
+    }
+
+    /// Prints a detailed view of the token transfer using `Display`.
     pub fn display_detailed(&self) {
         println!("{}", self);
     }
+}
 
-    /// Prints a compact, one-line summary of the transaction.
-    pub fn display_compact(&self) {
-        println!(
-            "{} -> {} | {} ETH",
-            &self.from[..8],
-            &self.to[..8],
-            self.quantity
-        );
+/// A single ERC-721 or ERC-1155 transfer involving the tracked wallet.
+#[derive(Debug)]
+pub struct NftTransfer {
+    /// Chain the transfer was made on.
+    pub chain: Chain,
+    /// Hash of the transaction that emitted the transfer.
+    pub hash: String,
+    /// Number of the block the transfer was included in.
+    pub block_number: u64,
+    /// Position of the emitting log within the block. A batch transfer emits
+    /// several transfers from the same log.
+    pub log_index: u64,
+    /// Unix timestamp of the block.
+    pub timestamp: u64,
+    /// Collection contract address, lowercase.
+    pub contract: String,
+    /// Collection ticker, empty if the contract does not expose one.
+    pub symbol: String,
+    /// Token standard of the collection.
+    pub standard: NftStandard,
+    /// Id of the token within the collection.
+    pub token_id: U256,
+    /// Number of tokens moved; always 1 for ERC-721.
+    pub amount: U256,
+    /// Sender address.
+    pub from: String,
+    /// Recipient address.
+    pub to: String,
+    /// Whether the tracked wallet sent or received the token.
+    pub direction: Direction,
+    /// Date of the transfer (as a string).
+    pub date: String,
+}
+
+impl fmt::Display for NftTransfer {
+    /// Formats the NFT transfer details in a human-readable multiline format.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} transfer ({}) on {}\n\
+             From: {}\n\
+             To: {}\n\
+             Token: {} #{} x{}\n\
+             Collection: {}\n",
+            self.standard,
+            self.direction,
+            self.date,
+            self.from,
+            self.to,
+            self.symbol,
+            self.token_id,
+            self.amount,
+            self.contract,
+        )
+    }
+}
+
+impl NftTransfer {
+    /// Prints a detailed view of the NFT transfer using `Display`.
+    pub fn display_detailed(&self) {
+        println!("{}", self);
+    }
+}
+
+/// An NFT currently held by the tracked wallet, rebuilt from its transfer history.
+#[derive(Debug)]
+pub struct NftHolding {
+    /// Collection contract address, lowercase.
+    pub contract: String,
+    /// Collection ticker.
+    pub symbol: String,
+    /// Token standard of the collection.
+    pub standard: NftStandard,
+    /// Id of the token within the collection.
+    pub token_id: U256,
+    /// Number of tokens held; always 1 for ERC-721.
+    pub amount: U256,
+    /// Date the wallet last received the token.
+    pub acquired: String,
+}
+
+impl fmt::Display for NftHolding {
+    /// Formats the holding as a one-line summary.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} #{} x{} ({} {}) acquired {}",
+            self.symbol, self.token_id, self.amount, self.standard, self.contract, self.acquired
+        )
     }
 }
 
+/// A single native-currency transfer singled out by [`Statistics`].
+#[derive(Debug, Clone)]
+pub struct NotableTransfer {
+    /// Hash of the transaction.
+    pub hash: String,
+    /// Value transferred.
+    pub quantity: Wei,
+    /// UTC day of the transfer.
+    pub date: NaiveDate,
+}
+
 /// Summary statistics for a wallet's transaction history.
+///
+/// Value statistics only consider transactions that moved value to or from
+/// another address; zero-value calls and self-transfers would skew them.
 #[derive(Debug)]
 pub struct Statistics {
+    /// Chain the wallet was analyzed on.
+    pub chain: Chain,
     /// Wallet address being analyzed.
-    pub address: String,
-    /// Total number of transactions.
-    pub total_transactions: String,
+    pub address: Address,
+    /// Total number of transactions, internal ones included.
+    pub total_transactions: usize,
     /// Average gas used per transaction.
     pub average_gas: f64,
-    /// Average ETH transferred per transaction.
-    pub average_eth: f64,
-    /// Date of the wallet’s first transaction.
-    pub first_transaction: String,
+    /// Average value transferred per transaction, exact in wei.
+    pub average_eth: Wei,
+    /// Median value of the transfers.
+    pub median_value: Wei,
+    /// 90th percentile value of the transfers.
+    pub p90_value: Wei,
+    /// Largest transfer received, if any.
+    pub largest_inflow: Option<NotableTransfer>,
+    /// Largest transfer sent, if any.
+    pub largest_outflow: Option<NotableTransfer>,
+    /// Value received by the wallet, internal transfers included.
+    pub total_inflow: Wei,
+    /// Value sent by the wallet, fees excluded.
+    pub total_outflow: Wei,
+    /// UTC day of the wallet's first transaction.
+    pub first_transaction: NaiveDate,
+    /// UTC day of the wallet's latest transaction or token transfer.
+    pub last_activity: NaiveDate,
+    /// Number of distinct UTC days with a transaction or token transfer.
+    pub active_days: usize,
+    /// Number of distinct addresses the wallet exchanged value or tokens with.
+    pub unique_counterparties: usize,
+    /// Total number of ERC-20 token transfers.
+    pub total_token_transfers: usize,
+    /// Number of distinct ERC-20 tokens transferred.
+    pub distinct_tokens: usize,
+}
+
+impl Statistics {
+    /// Returns inflow minus outflow in Ether, signed.
+    pub fn net_flow(&self) -> String {
+        format_ether_delta(self.total_inflow, self.total_outflow)
+    }
+
+    /// Formats a notable transfer as `1.5 ETH on 2024-03-01 (0x…)`, or `none`.
+    fn format_notable(&self, transfer: &Option<NotableTransfer>) -> String {
+        match transfer {
+            Some(transfer) => format!(
+                "{} {} on {} ({})",
+                transfer.quantity.to_ether_string(),
+                self.chain.native_symbol(),
+                transfer.date,
+                transfer.hash
+            ),
+            None => "none".to_string(),
+        }
+    }
 }
 
 impl fmt::Display for Statistics {
     /// Formats the wallet statistics in a readable format for display.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = self.chain.native_symbol();
         write!(
             f,
-            "Wallet address: {}\n\
+            "Wallet address: {} ({})\n\
              Total number of transactions: {}\n\
              Average gas per transaction: {:.2}\n\
-             Average ETH per transaction: {}\n\
-             Date of first transaction: {}\n",
+             Average {} per transaction: {}\n\
+             Median transfer: {} {}\n\
+             90th percentile transfer: {} {}\n\
+             Largest inflow: {}\n\
+             Largest outflow: {}\n\
+             Total inflow: {} {}\n\
+             Total outflow: {} {}\n\
+             Net flow: {} {}\n\
+             Date of first transaction: {}\n\
+             Last activity: {}\n\
+             Active days: {}\n\
+             Unique counterparties: {}\n\
+             Total number of token transfers: {}\n\
+             Distinct tokens transferred: {}\n",
             self.address,
+            self.chain,
             self.total_transactions,
             self.average_gas,
-            self.average_eth,
+            symbol,
+            self.average_eth.to_ether_string(),
+            self.median_value.to_ether_string(),
+            symbol,
+            self.p90_value.to_ether_string(),
+            symbol,
+            self.format_notable(&self.largest_inflow),
+            self.format_notable(&self.largest_outflow),
+            self.total_inflow.to_ether_string(),
+            symbol,
+            self.total_outflow.to_ether_string(),
+            symbol,
+            self.net_flow(),
+            symbol,
             self.first_transaction,
+            self.last_activity,
+            self.active_days,
+            self.unique_counterparties,
+            self.total_token_transfers,
+            self.distinct_tokens,
+        )
+    }
+}
+
+/// Summary of the wallet's dealings with one counterparty address.
+#[derive(Debug)]
+pub struct Counterparty {
+    /// Counterparty address, lowercase.
+    pub address: String,
+    /// Number of transactions between the wallet and the counterparty.
+    pub transactions: usize,
+    /// Value received from the counterparty.
+    pub volume_in: Wei,
+    /// Value sent to the counterparty.
+    pub volume_out: Wei,
+    /// UTC day of the first interaction.
+    pub first_interaction: NaiveDate,
+    /// UTC day of the latest interaction.
+    pub last_interaction: NaiveDate,
+    /// Whether the counterparty holds contract code, if known.
+    pub is_contract: Option<bool>,
+}
+
+impl Counterparty {
+    /// Prints a one-line summary, with the counterparty's ENS name from `names` if it has one.
+    pub fn display_compact(&self, chain: Chain, names: &HashMap<String, String>) {
+        let label = match names.get(&self.address) {
+            Some(name) => format!("{} ({})", name, self.address),
+            None => self.address.clone(),
+        };
+        println!(
+            "{}{} | {} tx | in {} {} | out {} {} | {} to {}",
+            label,
+            match self.is_contract {
+                Some(true) => " [contract]",
+                _ => "",
+            },
+            self.transactions,
+            self.volume_in.to_ether_string(),
+            chain.native_symbol(),
+            self.volume_out.to_ether_string(),
+            chain.native_symbol(),
+            self.first_interaction,
+            self.last_interaction,
+        );
+    }
+}
+
+/// Fees paid by a wallet over a set of transactions.
+#[derive(Debug)]
+pub struct FeeSummary {
+    /// Chain the fees were paid on.
+    pub chain: Chain,
+    /// Number of transactions looked at.
+    pub transactions: usize,
+    /// Number of those transactions sent by the wallet, whose fee it paid.
+    pub paid_transactions: usize,
+    /// Sum of the fees paid.
+    pub total_fees: Wei,
+    /// Average fee per paid transaction.
+    pub average_fee: Wei,
+    /// Average units of gas used per paid transaction.
+    pub average_gas_used: f64,
+    /// Average price paid per unit of gas.
+    pub average_gas_price: Wei,
+    /// Total fees in each quote currency, at the price of each transaction's day.
+    pub total_fiat: Vec<FiatAmount>,
+}
+
+impl fmt::Display for FeeSummary {
+    /// Formats the fee totals and averages in a readable format for display.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let average_fiat: Vec<FiatAmount> = self
+            .total_fiat
+            .iter()
+            .map(|total| FiatAmount {
+                currency: total.currency.clone(),
+                amount: total.amount / self.paid_transactions.max(1) as f64,
+            })
+            .collect();
+
+        write!(
+            f,
+            "Transactions: {} ({} sent by the wallet)\n\
+             Total fees paid: {} {}{}\n\
+             Average fee: {} {}{}\n\
+             Average gas used: {:.0}\n\
+             Average gas price: {} gwei\n",
+            self.transactions,
+            self.paid_transactions,
+            self.total_fees.to_ether_string(),
+            self.chain.native_symbol(),
+            format_fiat_values(&self.total_fiat),
+            self.average_fee.to_ether_string(),
+            self.chain.native_symbol(),
+            format_fiat_values(&average_fiat),
+            self.average_gas_used,
+            self.average_gas_price.to_gwei_string(),
         )
     }
 }
 
+/// Balance of one ERC-20 token held by a wallet.
+#[derive(Debug)]
+pub struct TokenBalance {
+    /// Token contract address, lowercase.
+    pub contract: String,
+    /// Token ticker as reported by `symbol()`.
+    pub symbol: String,
+    /// Number of decimals as reported by `decimals()`.
+    pub decimals: u8,
+    /// Raw balance in the token's smallest unit.
+    pub balance: U256,
+}
+
+impl fmt::Display for TokenBalance {
+    /// Formats the balance with the token's decimals, followed by its symbol and contract.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({})",
+            format_units(self.balance, self.decimals),
+            self.symbol,
+            self.contract
+        )
+    }
+}
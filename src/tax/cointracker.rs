@@ -0,0 +1,47 @@
+use super::{TaxAmount, TaxEntry, TaxProfile};
+
+/// CoinTracker transaction import format.
+///
+/// Dates are UTC in `MM/DD/YYYY HH:MM:SS`. CoinTracker infers the kind of
+/// each row from which quantity columns are filled.
+pub struct CoinTracker;
+
+impl TaxProfile for CoinTracker {
+    fn name(&self) -> &'static str {
+        "CoinTracker"
+    }
+
+    fn file_name(&self) -> &'static str {
+        "cointracker.csv"
+    }
+
+    fn header(&self) -> &'static [&'static str] {
+        &[
+            "Date",
+            "Received Quantity",
+            "Received Currency",
+            "Sent Quantity",
+            "Sent Currency",
+            "Fee Amount",
+            "Fee Currency",
+            "Tag",
+        ]
+    }
+
+    fn record(&self, entry: &TaxEntry) -> Vec<String> {
+        let (received_quantity, received_currency) = TaxAmount::columns(entry.received.as_ref());
+        let (sent_quantity, sent_currency) = TaxAmount::columns(entry.sent.as_ref());
+        let (fee_amount, fee_currency) = TaxAmount::columns(entry.fee.as_ref());
+
+        vec![
+            entry.format_date("%m/%d/%Y %H:%M:%S"),
+            received_quantity,
+            received_currency,
+            sent_quantity,
+            sent_currency,
+            fee_amount,
+            fee_currency,
+            String::new(),
+        ]
+    }
+}
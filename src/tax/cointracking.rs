@@ -0,0 +1,71 @@
+use super::{TaxAmount, TaxEntry, TaxEntryKind, TaxProfile};
+
+/// CoinTracking custom exchange import format.
+///
+/// Every row has an explicit type. Fee-only entries are written as
+/// `Other Fee` rows, which CoinTracking expects in the sell columns.
+pub struct CoinTracking;
+
+impl TaxProfile for CoinTracking {
+    fn name(&self) -> &'static str {
+        "CoinTracking"
+    }
+
+    fn file_name(&self) -> &'static str {
+        "cointracking.csv"
+    }
+
+    fn header(&self) -> &'static [&'static str] {
+        &[
+            "Type",
+            "Buy Amount",
+            "Buy Currency",
+            "Sell Amount",
+            "Sell Currency",
+            "Fee",
+            "Fee Currency",
+            "Exchange",
+            "Trade-Group",
+            "Comment",
+            "Date",
+            "Tx-ID",
+        ]
+    }
+
+    fn record(&self, entry: &TaxEntry) -> Vec<String> {
+        let (buy_amount, buy_currency) = TaxAmount::columns(entry.received.as_ref());
+        let kind = entry.kind();
+        let (sell_amount, sell_currency, fee, fee_currency) = match kind {
+            TaxEntryKind::Fee => {
+                let (amount, currency) = TaxAmount::columns(entry.fee.as_ref());
+                (amount, currency, String::new(), String::new())
+            }
+            _ => {
+                let (sell_amount, sell_currency) = TaxAmount::columns(entry.sent.as_ref());
+                let (fee, fee_currency) = TaxAmount::columns(entry.fee.as_ref());
+                (sell_amount, sell_currency, fee, fee_currency)
+            }
+        };
+
+        vec![
+            match kind {
+                TaxEntryKind::Deposit => "Deposit",
+                TaxEntryKind::Withdrawal => "Withdrawal",
+                TaxEntryKind::Trade => "Trade",
+                TaxEntryKind::Fee => "Other Fee",
+            }
+            .to_string(),
+            buy_amount,
+            buy_currency,
+            sell_amount,
+            sell_currency,
+            fee,
+            fee_currency,
+            format!("{} wallet", entry.chain),
+            String::new(),
+            entry.description.clone(),
+            entry.format_date("%Y-%m-%d %H:%M:%S"),
+            entry.hash.clone(),
+        ]
+    }
+}
@@ -0,0 +1,66 @@
+use super::{TaxAmount, TaxEntry, TaxEntryKind, TaxProfile};
+
+/// Koinly universal import format.
+///
+/// Dates are UTC; Koinly reads the fee and its currency from dedicated
+/// columns and uses the net worth columns as the fiat value of the entry.
+pub struct Koinly;
+
+impl TaxProfile for Koinly {
+    fn name(&self) -> &'static str {
+        "Koinly"
+    }
+
+    fn file_name(&self) -> &'static str {
+        "koinly.csv"
+    }
+
+    fn header(&self) -> &'static [&'static str] {
+        &[
+            "Date",
+            "Sent Amount",
+            "Sent Currency",
+            "Received Amount",
+            "Received Currency",
+            "Fee Amount",
+            "Fee Currency",
+            "Net Worth Amount",
+            "Net Worth Currency",
+            "Label",
+            "Description",
+            "TxHash",
+        ]
+    }
+
+    fn record(&self, entry: &TaxEntry) -> Vec<String> {
+        let (sent_amount, sent_currency) = TaxAmount::columns(entry.sent.as_ref());
+        let (received_amount, received_currency) = TaxAmount::columns(entry.received.as_ref());
+        let (fee_amount, fee_currency) = TaxAmount::columns(entry.fee.as_ref());
+        let (worth_amount, worth_currency) = match &entry.value {
+            Some(value) => (
+                value.currency.format_plain(value.amount),
+                value.currency.code().to_string(),
+            ),
+            None => (String::new(), String::new()),
+        };
+
+        vec![
+            entry.format_date("%Y-%m-%d %H:%M:%S UTC"),
+            sent_amount,
+            sent_currency,
+            received_amount,
+            received_currency,
+            fee_amount,
+            fee_currency,
+            worth_amount,
+            worth_currency,
+            // Koinly treats outgoing entries labelled `cost` as deductible expenses.
+            match entry.kind() {
+                TaxEntryKind::Fee => "cost".to_string(),
+                _ => String::new(),
+            },
+            entry.description.clone(),
+            entry.hash.clone(),
+        ]
+    }
+}
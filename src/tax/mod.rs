@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use csv::Writer;
+use serde::Deserialize;
+
+use crate::{
+    amount::Wei,
+    currency::{Currency, FiatAmount},
+    enums::Direction,
+    errors::WalletError,
+    ethereum::chain::Chain,
+    structs::{TokenTransfer, Transaction},
+};
+
+pub mod cointracker;
+pub mod cointracking;
+pub mod koinly;
+
+use cointracker::CoinTracker;
+use cointracking::CoinTracking;
+use koinly::Koinly;
+
+/// A CSV layout understood by a crypto tax tool.
+pub trait TaxProfile: Send + Sync {
+    /// Name of the tool, used in messages.
+    fn name(&self) -> &'static str;
+
+    /// Name of the file the export is written to.
+    fn file_name(&self) -> &'static str;
+
+    /// Column names, in order.
+    fn header(&self) -> &'static [&'static str];
+
+    /// Formats one entry as a row matching [`TaxProfile::header`].
+    fn record(&self, entry: &TaxEntry) -> Vec<String>;
+}
+
+/// Layout of the files written by the export action.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Statistics, transactions, token transfers and NFTs in the tracker's own layout.
+    #[default]
+    Standard,
+    /// Koinly universal import format.
+    Koinly,
+    /// CoinTracker transaction import format.
+    Cointracker,
+    /// CoinTracking custom exchange import format.
+    Cointracking,
+}
+
+impl ExportFormat {
+    /// Returns the tax profile writing this format, or `None` for the standard layout.
+    pub fn profile(self) -> Option<Box<dyn TaxProfile>> {
+        match self {
+            ExportFormat::Standard => None,
+            ExportFormat::Koinly => Some(Box::new(Koinly)),
+            ExportFormat::Cointracker => Some(Box::new(CoinTracker)),
+            ExportFormat::Cointracking => Some(Box::new(CoinTracking)),
+        }
+    }
+}
+
+/// An amount of a given asset, already formatted in whole units.
+#[derive(Debug, Clone)]
+pub struct TaxAmount {
+    pub amount: String,
+    pub currency: String,
+}
+
+impl TaxAmount {
+    /// Returns the amount and currency columns of an optional amount, both empty when absent.
+    pub fn columns(amount: Option<&TaxAmount>) -> (String, String) {
+        match amount {
+            Some(amount) => (amount.amount.clone(), amount.currency.clone()),
+            None => (String::new(), String::new()),
+        }
+    }
+}
+
+/// What a [`TaxEntry`] does to the wallet's holdings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaxEntryKind {
+    /// Assets were received.
+    Deposit,
+    /// Assets were sent.
+    Withdrawal,
+    /// Assets were sent and others received in the same transaction.
+    Trade,
+    /// Only a fee was paid, e.g. for a contract call or a self-transfer.
+    Fee,
+}
+
+/// One movement of the wallet's holdings, independent of any tool's layout.
+#[derive(Debug, Clone)]
+pub struct TaxEntry {
+    /// Chain the movement happened on.
+    pub chain: Chain,
+    /// Unix timestamp of the block.
+    pub timestamp: u64,
+    /// Hash of the transaction.
+    pub hash: String,
+    /// Assets that left the wallet.
+    pub sent: Option<TaxAmount>,
+    /// Assets that entered the wallet.
+    pub received: Option<TaxAmount>,
+    /// Network fee paid by the wallet.
+    pub fee: Option<TaxAmount>,
+    /// Fiat value of the moved native currency at the time of the movement.
+    pub value: Option<FiatAmount>,
+    /// Short human-readable description.
+    pub description: String,
+}
+
+impl TaxEntry {
+    /// Returns the kind of the entry, derived from what was sent and received.
+    pub fn kind(&self) -> TaxEntryKind {
+        match (&self.sent, &self.received) {
+            (Some(_), Some(_)) => TaxEntryKind::Trade,
+            (Some(_), None) => TaxEntryKind::Withdrawal,
+            (None, Some(_)) => TaxEntryKind::Deposit,
+            (None, None) => TaxEntryKind::Fee,
+        }
+    }
+
+    /// Formats the block time in UTC with a `chrono` format string.
+    pub fn format_date(&self, format: &str) -> String {
+        DateTime::<Utc>::from_timestamp(self.timestamp as i64, 0)
+            .map(|date| date.format(format).to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Turns a wallet's history into tax entries, oldest first.
+///
+/// Native transfers become deposits or withdrawals, with the fee attached
+/// to the transactions the wallet sent. A token transfer emitted by one of
+/// those transactions is merged into its entry when the matching side is
+/// still free, so a swap of ETH for tokens becomes a single trade and a
+/// token transfer carries the fee of the transaction that sent it; when it
+/// emitted none, a transaction that moved no value stays a fee-only entry.
+/// A reverted transaction is exported as its fee only.
+///
+/// # Arguments
+/// * `chain` - Chain the history belongs to.
+/// * `transactions` - Native transactions, valued if a fiat value is wanted.
+/// * `token_transfers` - ERC-20 transfers of the wallet.
+/// * `currency` - Currency of the fiat values to include.
+pub fn tax_entries(
+    chain: Chain,
+    transactions: &[Transaction],
+    token_transfers: &[TokenTransfer],
+    currency: &Currency,
+) -> Vec<TaxEntry> {
+    let native = |quantity: Wei| TaxAmount {
+        amount: quantity.to_ether_string(),
+        currency: chain.native_symbol().to_string(),
+    };
+
+    let mut entries = Vec::new();
+    // Entries by hash that a token transfer of the same transaction can still join.
+    let mut open: HashMap<&str, usize> = HashMap::new();
+
+    for tx in transactions {
+        let direction = tx.direction;
+        let fee = tx.fee();
+        let fee = (!tx.internal && direction.is_sent() && fee != Wei::ZERO).then(|| native(fee));
+        // A reverted transaction moved nothing, but its sender still paid the fee.
+        let moved = tx.quantity != Wei::ZERO
+            && (direction.is_inflow() || direction.is_outflow())
+            && !tx.is_error;
+
+        if !moved && fee.is_none() {
+            continue;
+        }
+
+        let entry = TaxEntry {
+            chain,
+            timestamp: tx.timestamp,
+            hash: tx.hash.clone(),
+            sent: (moved && direction.is_outflow()).then(|| native(tx.quantity)),
+            received: (moved && direction.is_inflow()).then(|| native(tx.quantity)),
+            fee,
+            value: moved
+                .then(|| {
+                    tx.fiat_values
+                        .iter()
+                        .find(|value| &value.currency == currency)
+                        .cloned()
+                })
+                .flatten(),
+            description: match (tx.internal, moved, direction) {
+                (true, _, _) => "Internal transfer".to_string(),
+                (false, false, _) if tx.is_error => "Failed transaction fee".to_string(),
+                (false, false, Direction::SelfTransfer) => "Self-transfer fee".to_string(),
+                (false, false, _) => "Transaction fee".to_string(),
+                (false, true, Direction::ContractCreation) => "Contract creation".to_string(),
+                (false, true, _) => format!("{} transfer", chain.native_symbol()),
+            },
+        };
+
+        if !tx.internal {
+            open.insert(&tx.hash, entries.len());
+        }
+        entries.push(entry);
+    }
+
+    for transfer in token_transfers {
+        let amount = TaxAmount {
+            amount: transfer.formatted_amount(),
+            currency: transfer.symbol.clone(),
+        };
+        let (sent, received) = match transfer.direction {
+            Direction::In => (None, Some(amount)),
+            Direction::Out => (Some(amount), None),
+            Direction::SelfTransfer | Direction::ContractCreation | Direction::Unrelated => {
+                continue;
+            }
+        };
+        let description = format!("{} transfer", transfer.symbol);
+
+        if let Some(&index) = open.get(transfer.hash.as_str()) {
+            let entry = &mut entries[index];
+            let free = match transfer.direction {
+                Direction::In => entry.received.is_none(),
+                _ => entry.sent.is_none(),
+            };
+            if free {
+                entry.description = match entry.kind() {
+                    TaxEntryKind::Fee => description,
+                    _ => "Swap".to_string(),
+                };
+                entry.sent = entry.sent.take().or(sent);
+                entry.received = entry.received.take().or(received);
+                open.remove(transfer.hash.as_str());
+                continue;
+            }
+        }
+
+        entries.push(TaxEntry {
+            chain,
+            timestamp: transfer.timestamp,
+            hash: transfer.hash.clone(),
+            sent,
+            received,
+            fee: None,
+            value: None,
+            description,
+        });
+    }
+
+    entries.sort_by_key(|entry| entry.timestamp);
+    entries
+}
+
+/// Writes tax entries to `path` in the layout of `profile`.
+pub fn write_tax_csv(
+    profile: &dyn TaxProfile,
+    entries: &[TaxEntry],
+    path: &Path,
+) -> Result<(), WalletError> {
+    let mut writer = Writer::from_path(path)?;
+
+    writer.write_record(profile.header())?;
+    for entry in entries {
+        writer.write_record(profile.record(entry))?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use primitive_types::U256;
+
+    use super::*;
+
+    /// 2024-01-01 00:00:00 UTC.
+    const JAN_1: u64 = 1_704_067_200;
+
+    /// Builds a transfer of `amount` whole USDC emitted by the transaction `hash`.
+    fn usdc(direction: Direction, amount: u64, hash: &str, timestamp: u64) -> TokenTransfer {
+        TokenTransfer {
+            chain: Chain::Ethereum,
+            hash: hash.to_string(),
+            log_index: 0,
+            block_number: timestamp / 12,
+            timestamp,
+            contract: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".to_string(),
+            symbol: "USDC".to_string(),
+            decimals: 6,
+            from: String::new(),
+            to: String::new(),
+            amount: U256::from(amount) * U256::exp10(6),
+            direction,
+            date: String::new(),
+        }
+    }
+
+    fn entries(transactions: &[Transaction], transfers: &[TokenTransfer]) -> Vec<TaxEntry> {
+        tax_entries(
+            Chain::Ethereum,
+            transactions,
+            transfers,
+            &Currency::default(),
+        )
+    }
+
+    fn amount(amount: Option<&TaxAmount>) -> Option<(&str, &str)> {
+        amount.map(|amount| (amount.amount.as_str(), amount.currency.as_str()))
+    }
+
+    #[test]
+    fn native_transfers_become_deposits_and_withdrawals() {
+        let mut deposit = Transaction::received(1500, JAN_1);
+        deposit.fiat_values.push(FiatAmount {
+            currency: Currency::default(),
+            amount: 3000.0,
+        });
+        let entries = entries(
+            &[
+                deposit,
+                Transaction::sent(Direction::Out, 500, 2, JAN_1 + 60),
+            ],
+            &[],
+        );
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].kind(), TaxEntryKind::Deposit);
+        assert_eq!(amount(entries[0].received.as_ref()), Some(("1.5", "ETH")));
+        assert!(entries[0].fee.is_none());
+        assert_eq!(
+            entries[0].value.as_ref().map(|value| value.amount),
+            Some(3000.0)
+        );
+        assert_eq!(entries[1].kind(), TaxEntryKind::Withdrawal);
+        assert_eq!(amount(entries[1].sent.as_ref()), Some(("0.5", "ETH")));
+        assert_eq!(amount(entries[1].fee.as_ref()), Some(("0.002", "ETH")));
+        assert_eq!(entries[1].description, "ETH transfer");
+    }
+
+    #[test]
+    fn failed_transaction_is_a_fee_entry() {
+        let mut failed = Transaction::sent(Direction::Out, 500, 2, JAN_1);
+        failed.is_error = true;
+        let entries = entries(&[failed], &[]);
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind(), TaxEntryKind::Fee);
+        assert!(entries[0].sent.is_none());
+        assert!(entries[0].value.is_none());
+        assert_eq!(amount(entries[0].fee.as_ref()), Some(("0.002", "ETH")));
+        assert_eq!(entries[0].description, "Failed transaction fee");
+    }
+
+    #[test]
+    fn transactions_without_value_or_fee_are_skipped() {
+        let entries = entries(
+            &[
+                Transaction::sample(Direction::In, Wei::ZERO, JAN_1),
+                Transaction::sample(Direction::Unrelated, Wei::milliether(100), JAN_1),
+                Transaction::sent(Direction::SelfTransfer, 100, 1, JAN_1 + 60),
+            ],
+            &[],
+        );
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind(), TaxEntryKind::Fee);
+        assert_eq!(entries[0].description, "Self-transfer fee");
+    }
+
+    #[test]
+    fn token_transfer_joins_its_transaction() {
+        let swap = Transaction::sent(Direction::Out, 1000, 3, JAN_1);
+        let approval = Transaction::sent(Direction::Out, 0, 1, JAN_1 + 60);
+        let transfers = [
+            usdc(Direction::In, 2000, &swap.hash, JAN_1),
+            usdc(Direction::Out, 50, &approval.hash, JAN_1 + 60),
+            usdc(Direction::In, 10, "0xairdrop", JAN_1 + 120),
+        ];
+        let entries = entries(&[swap, approval], &transfers);
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].kind(), TaxEntryKind::Trade);
+        assert_eq!(entries[0].description, "Swap");
+        assert_eq!(amount(entries[0].received.as_ref()), Some(("2000", "USDC")));
+        assert_eq!(entries[1].kind(), TaxEntryKind::Withdrawal);
+        assert_eq!(entries[1].description, "USDC transfer");
+        assert_eq!(amount(entries[1].fee.as_ref()), Some(("0.001", "ETH")));
+        assert_eq!(entries[2].kind(), TaxEntryKind::Deposit);
+        assert!(entries[2].fee.is_none());
+    }
+
+    #[test]
+    fn profiles_write_rows_matching_their_header() {
+        let mut failed = Transaction::sent(Direction::Out, 500, 2, JAN_1);
+        failed.is_error = true;
+        let entries = entries(
+            &[
+                failed,
+                Transaction::sent(Direction::Out, 500, 2, JAN_1 + 60),
+            ],
+            &[],
+        );
+
+        for format in [
+            ExportFormat::Koinly,
+            ExportFormat::Cointracker,
+            ExportFormat::Cointracking,
+        ] {
+            let profile = format.profile().unwrap();
+            for entry in &entries {
+                assert_eq!(profile.record(entry).len(), profile.header().len());
+            }
+        }
+        assert!(ExportFormat::Standard.profile().is_none());
+    }
+
+    #[test]
+    fn koinly_labels_fees_as_cost() {
+        let mut failed = Transaction::sent(Direction::Out, 500, 2, JAN_1);
+        failed.is_error = true;
+        let entries = entries(&[failed], &[]);
+
+        assert_eq!(
+            koinly::Koinly.record(&entries[0]),
+            [
+                "2024-01-01 00:00:00 UTC",
+                "",
+                "",
+                "",
+                "",
+                "0.002",
+                "ETH",
+                "",
+                "",
+                "cost",
+                "Failed transaction fee",
+                &entries[0].hash,
+            ]
+        );
+    }
+
+    #[test]
+    fn cointracker_fills_quantity_columns() {
+        let entries = entries(&[Transaction::sent(Direction::Out, 500, 2, JAN_1)], &[]);
+
+        assert_eq!(
+            cointracker::CoinTracker.record(&entries[0]),
+            [
+                "01/01/2024 00:00:00",
+                "",
+                "",
+                "0.5",
+                "ETH",
+                "0.002",
+                "ETH",
+                ""
+            ]
+        );
+    }
+
+    #[test]
+    fn cointracking_writes_fees_in_the_sell_columns() {
+        let entries = entries(
+            &[Transaction::sent(Direction::SelfTransfer, 500, 2, JAN_1)],
+            &[],
+        );
+        let record = cointracking::CoinTracking.record(&entries[0]);
+
+        assert_eq!(record[0], "Other Fee");
+        assert_eq!(record[3..7], ["0.002", "ETH", "", ""]);
+        assert_eq!(record[7], "ethereum wallet");
+        assert_eq!(record[10], "2024-01-01 00:00:00");
+    }
+}
@@ -1,4 +1,10 @@
-use crate::errors::WalletError;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::{
+    address::Address, balance_history::SamplingInterval, charts::ChartKind,
+    dashboard::DashboardSnapshot, enums::CounterpartySort, errors::WalletError, tax::ExportFormat,
+};
 use async_trait::async_trait;
 
 /// Defines display operations for wallet-related data such as balance, fiat value, transactions, and statistics.
@@ -6,13 +12,50 @@ use async_trait::async_trait;
 pub trait WalletDisplay {
     async fn display_balance(&self) -> Result<(), WalletError>;
     async fn display_fiat(&self) -> Result<(), WalletError>;
-    async fn display_transactions(&self) -> Result<(), WalletError>;
-    async fn display_average_gas(&self) -> Result<(), WalletError>;
+    async fn display_token_balances(&self) -> Result<(), WalletError>;
+    async fn display_nfts(&self) -> Result<(), WalletError>;
+    async fn display_transactions(&self, transactions_offset: i32) -> Result<(), WalletError>;
+    async fn display_fees(&self, transactions_offset: i32) -> Result<(), WalletError>;
     async fn display_statistics(&self) -> Result<(), WalletError>;
+    async fn display_cost_basis(&self) -> Result<(), WalletError>;
+    async fn display_counterparties(
+        &self,
+        sort: CounterpartySort,
+        limit: usize,
+    ) -> Result<(), WalletError>;
+    async fn display_balance_history(&self, interval: SamplingInterval) -> Result<(), WalletError>;
+    async fn display_chart(
+        &self,
+        kind: ChartKind,
+        interval: SamplingInterval,
+    ) -> Result<(), WalletError>;
+}
+
+/// Defines how the data shown by the dashboard is loaded.
+#[async_trait]
+pub trait WalletDashboard {
+    async fn dashboard_snapshot(&self, limit: usize) -> Result<DashboardSnapshot, WalletError>;
+}
+
+/// Defines the long-running watch mode.
+#[async_trait]
+pub trait WalletWatch {
+    async fn watch(&self, wallets: &[Address], interval: Duration) -> Result<(), WalletError>;
 }
 
 /// Defines export operations for wallet-related data.
 #[async_trait]
 pub trait WalletExport {
-    async fn export_to_csv(&self) -> Result<(), WalletError>;
+    async fn export_to_csv(&self, out_dir: &Path, format: ExportFormat) -> Result<(), WalletError>;
+    async fn export_balance_history(
+        &self,
+        path: &Path,
+        interval: SamplingInterval,
+    ) -> Result<(), WalletError>;
+    async fn export_counterparties(
+        &self,
+        path: &Path,
+        sort: CounterpartySort,
+        limit: usize,
+    ) -> Result<(), WalletError>;
 }